@@ -0,0 +1,283 @@
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::ron_file::{read_ron, write_ron};
+
+/// What the player wants to do, whichever device they did it with. Systems
+/// read `ButtonInput<Action>` instead of keys and buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    Reverse,
+    Pause,
+    Confirm,
+    Back,
+    ToggleFullscreen,
+    Up,
+    Down,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Reverse,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+        Action::ToggleFullscreen,
+        Action::Up,
+        Action::Down,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Reverse => "Reverse",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::ToggleFullscreen => "Fullscreen",
+            Action::Up => "Up",
+            Action::Down => "Down",
+        }
+    }
+
+    /// Whether the action is used while playing, as opposed to in menus.
+    /// Actions used in different places can share an input.
+    fn in_gameplay(self) -> bool {
+        matches!(
+            self,
+            Action::Reverse | Action::Pause | Action::ToggleFullscreen
+        )
+    }
+
+    fn in_menus(self) -> bool {
+        !matches!(self, Action::Reverse | Action::Pause)
+    }
+
+    fn default_bindings(self) -> Vec<Binding> {
+        use Binding::{Gamepad, Key, Mouse, Touch};
+        match self {
+            Action::Reverse => vec![
+                Key(KeyCode::Space),
+                Mouse(MouseButton::Left),
+                Touch,
+                Gamepad(GamepadButton::South),
+                Gamepad(GamepadButton::RightTrigger),
+            ],
+            Action::Pause => vec![Key(KeyCode::Escape), Gamepad(GamepadButton::Start)],
+            Action::Confirm => vec![
+                Key(KeyCode::Enter),
+                Key(KeyCode::NumpadEnter),
+                Key(KeyCode::Space),
+                Gamepad(GamepadButton::South),
+            ],
+            Action::Back => vec![Key(KeyCode::Escape), Gamepad(GamepadButton::East)],
+            Action::ToggleFullscreen => vec![Key(KeyCode::F12)],
+            Action::Up => vec![
+                Key(KeyCode::ArrowUp),
+                Key(KeyCode::KeyW),
+                Gamepad(GamepadButton::DPadUp),
+            ],
+            Action::Down => vec![
+                Key(KeyCode::ArrowDown),
+                Key(KeyCode::KeyS),
+                Gamepad(GamepadButton::DPadDown),
+            ],
+        }
+    }
+}
+
+/// One physical input that can trigger an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+    /// A tap anywhere on a touch screen.
+    Touch,
+}
+
+impl Binding {
+    pub fn name(self) -> String {
+        match self {
+            Binding::Key(key) => format!("{key:?}"),
+            Binding::Mouse(button) => format!("Mouse {button:?}"),
+            Binding::Gamepad(button) => format!("Pad {button:?}"),
+            Binding::Touch => "Tap".to_string(),
+        }
+    }
+}
+
+/// Where the bindings are saved. With `None` the defaults are used and
+/// nothing is written, which is what tests do.
+#[derive(Resource, Clone)]
+pub struct BindingsConfig {
+    pub path: Option<PathBuf>,
+}
+
+impl Default for BindingsConfig {
+    fn default() -> Self {
+        let path = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("SpinnyLock")
+            .join("controls.ron");
+        Self { path: Some(path) }
+    }
+}
+
+/// The inputs bound to each action. An action can have any number of them.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bindings {
+    pub actions: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            actions: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_bindings()))
+                .collect(),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.actions.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds `binding` to `action`, unless it is already bound to it.
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.actions.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.actions.insert(action, Vec::new());
+    }
+
+    /// Another action that `binding` would clash with if it were bound to
+    /// `action`, because both are used in the same place.
+    pub fn conflict(&self, action: Action, binding: Binding) -> Option<Action> {
+        Action::ALL.into_iter().find(|&other| {
+            other != action
+                && ((other.in_gameplay() && action.in_gameplay())
+                    || (other.in_menus() && action.in_menus()))
+                && self.get(other).contains(&binding)
+        })
+    }
+
+    /// Reads the bindings, falling back to the defaults for actions the file
+    /// doesn't mention, or for everything when it can't be parsed.
+    pub fn load(config: &BindingsConfig) -> Self {
+        let Some(path) = &config.path else {
+            return Self::default();
+        };
+        match read_ron::<Bindings>(path) {
+            Ok(Some(mut bindings)) => {
+                for action in Action::ALL {
+                    bindings
+                        .actions
+                        .entry(action)
+                        .or_insert_with(|| action.default_bindings());
+                }
+                bindings
+            }
+            Ok(None) => Self::default(),
+            Err(err) => {
+                warn!("Using the default controls: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, config: &BindingsConfig) {
+        let Some(path) = &config.path else {
+            return;
+        };
+        if let Err(err) = write_ron(path, self) {
+            warn!("Controls not saved: {err}");
+        }
+    }
+}
+
+pub struct ActionPlugin;
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonInput<Action>>()
+            .init_resource::<BindingsConfig>()
+            .init_resource::<Bindings>()
+            .add_systems(Startup, load_bindings)
+            .add_systems(PreUpdate, update_actions.after(InputSystem));
+    }
+}
+
+fn load_bindings(mut bindings: ResMut<Bindings>, config: Res<BindingsConfig>) {
+    *bindings = Bindings::load(&config);
+}
+
+/// An action is just pressed when any of its inputs was, and held while any
+/// of them is held. A second input pressed while the first is still held
+/// presses the action again.
+fn update_actions(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    gamepads: Query<&Gamepad>,
+    bindings: Res<Bindings>,
+    mut actions: ResMut<ButtonInput<Action>>,
+) {
+    actions.bypass_change_detection().clear();
+    for action in Action::ALL {
+        let mut just_pressed = false;
+        let mut held = false;
+        for binding in bindings.get(action) {
+            let (binding_just_pressed, binding_held) = match *binding {
+                Binding::Key(key) => (keyboard.just_pressed(key), keyboard.pressed(key)),
+                Binding::Mouse(button) => (mouse.just_pressed(button), mouse.pressed(button)),
+                Binding::Gamepad(button) => (
+                    gamepads.iter().any(|gamepad| gamepad.just_pressed(button)),
+                    gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+                ),
+                Binding::Touch => (touches.any_just_pressed(), touches.iter().next().is_some()),
+            };
+            just_pressed |= binding_just_pressed;
+            held |= binding_held;
+        }
+
+        if just_pressed {
+            actions.reset(action);
+            actions.press(action);
+        } else if !held {
+            actions.release(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_conflict_only_with_actions_used_in_the_same_place() {
+        let bindings = Bindings::default();
+        let space = Binding::Key(KeyCode::Space);
+        assert_eq!(bindings.conflict(Action::Up, space), Some(Action::Confirm));
+        assert_eq!(
+            bindings.conflict(Action::Pause, space),
+            Some(Action::Reverse)
+        );
+        assert_eq!(
+            bindings.conflict(Action::Back, Binding::Key(KeyCode::KeyF)),
+            None
+        );
+        // Reverse is only used while playing and Confirm only in menus.
+        assert_eq!(
+            bindings.conflict(Action::Reverse, Binding::Key(KeyCode::Enter)),
+            None
+        );
+    }
+}
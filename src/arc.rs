@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+/// Wraps an angle into `(-PI, PI]`.
+pub fn wrap_angle(angle: f32) -> f32 {
+    let wrapped = angle.rem_euclid(TAU);
+    if wrapped > PI {
+        wrapped - TAU
+    } else {
+        wrapped
+    }
+}
+
+/// Returns true if two arcs on the same ring overlap. Each arc is given by
+/// the angle of its centre and its half-width, both in radians.
+pub fn arcs_overlap(a_center: f32, a_half_width: f32, b_center: f32, b_half_width: f32) -> bool {
+    wrap_angle(a_center - b_center).abs() <= a_half_width + b_half_width
+}
+
+/// Where an entity sits around the ring. Gameplay updates it on fixed ticks
+/// and the `Transform` is interpolated from `previous` to `current` between
+/// them.
+#[derive(Component, Clone, Copy, Default)]
+pub struct RingAngle {
+    pub current: f32,
+    pub previous: f32,
+}
+
+impl RingAngle {
+    pub fn new(angle: f32) -> Self {
+        Self {
+            current: angle,
+            previous: angle,
+        }
+    }
+
+    /// Moves to `angle` without interpolating through the angles in between.
+    pub fn teleport(&mut self, angle: f32) {
+        *self = Self::new(angle);
+    }
+
+    pub fn interpolated(&self, alpha: f32) -> f32 {
+        self.previous + wrap_angle(self.current - self.previous) * alpha
+    }
+}
@@ -0,0 +1,442 @@
+use bevy::{
+    audio::{AddAudioSource, AudioSink, AudioSinkPlayback, Decodable, Source, Volume},
+    prelude::*,
+};
+use std::{
+    f32::consts::TAU,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    difficulty::DifficultyCurve,
+    gameplay::{Missed, RotationSpeed, TargetHit},
+    grade::Grade,
+    settings::{Settings, SettingsPlugin},
+    state::GameState,
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Tempo of the music while the line spins at the curve's starting speed.
+const BASE_TEMPO: f32 = 100.;
+const MAX_TEMPO: f32 = 200.;
+/// A speed-up sound plays each time the line gets this much faster.
+const SPEED_UP_STEP: f32 = 1.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Triangle,
+    Sawtooth,
+}
+
+impl Waveform {
+    /// The wave's value at `phase`, which goes from 0 to 1 over one period.
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.
+                } else {
+                    -1.
+                }
+            }
+            Waveform::Triangle => 1. - 4. * (phase - 0.5).abs(),
+            Waveform::Sawtooth => 2. * phase - 1.,
+        }
+    }
+}
+
+/// A sound effect generated from a single oscillator whose frequency slides
+/// from `start_frequency` to `end_frequency`, fading out by the end.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct Tone {
+    pub waveform: Waveform,
+    pub start_frequency: f32,
+    pub end_frequency: f32,
+    pub duration: f32,
+}
+
+impl Decodable for Tone {
+    type DecoderItem = f32;
+    type Decoder = ToneDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        ToneDecoder {
+            tone: self.clone(),
+            phase: 0.,
+            index: 0,
+            len: (self.duration.max(0.) * SAMPLE_RATE as f32) as u32,
+        }
+    }
+}
+
+pub struct ToneDecoder {
+    tone: Tone,
+    phase: f32,
+    index: u32,
+    len: u32,
+}
+
+impl Iterator for ToneDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.index >= self.len {
+            return None;
+        }
+        let t = self.index as f32 / self.len as f32;
+        let frequency = self.tone.start_frequency.lerp(self.tone.end_frequency, t);
+        // A few milliseconds of attack avoid a click at the start.
+        let attack = (self.index as f32 / (SAMPLE_RATE as f32 * 0.005)).min(1.);
+        let sample = self.tone.waveform.sample(self.phase) * attack * (1. - t).powi(2);
+        self.phase = (self.phase + frequency / SAMPLE_RATE as f32).fract();
+        self.index += 1;
+        Some(sample * 0.5)
+    }
+}
+
+impl Source for ToneDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.len - self.index) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.tone.duration.max(0.)))
+    }
+}
+
+/// Roots of the bars of the background track, in Hz.
+const BAR_ROOTS: [f32; 4] = [110., 87.31, 130.81, 98.];
+/// Each eighth note of a bar as a multiple of the bar's root.
+const BAR_STEPS: [f32; 8] = [1., 1.5, 2., 1.5, 1., 2., 3., 2.];
+
+/// An endless background track. Its tempo, in beats per minute, can be
+/// changed while it plays and takes effect from the next note.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct MusicTrack {
+    tempo: Arc<AtomicU32>,
+}
+
+impl MusicTrack {
+    pub fn new(tempo: f32) -> Self {
+        Self {
+            tempo: Arc::new(AtomicU32::new(tempo.to_bits())),
+        }
+    }
+
+    pub fn tempo(&self) -> f32 {
+        f32::from_bits(self.tempo.load(Ordering::Relaxed))
+    }
+
+    pub fn set_tempo(&self, tempo: f32) {
+        self.tempo.store(tempo.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl Decodable for MusicTrack {
+    type DecoderItem = f32;
+    type Decoder = MusicDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        MusicDecoder {
+            track: self.clone(),
+            step: 0,
+            step_index: 0,
+            step_len: 0,
+            phase: 0.,
+        }
+    }
+}
+
+pub struct MusicDecoder {
+    track: MusicTrack,
+    step: usize,
+    step_index: u32,
+    step_len: u32,
+    phase: f32,
+}
+
+impl Iterator for MusicDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.step_index >= self.step_len {
+            if self.step_len > 0 {
+                self.step = (self.step + 1) % (BAR_ROOTS.len() * BAR_STEPS.len());
+            }
+            let seconds_per_step = 30. / self.track.tempo().clamp(1., MAX_TEMPO * 2.);
+            self.step_len = (seconds_per_step * SAMPLE_RATE as f32) as u32;
+            self.step_index = 0;
+        }
+        let root = BAR_ROOTS[self.step / BAR_STEPS.len()];
+        let frequency = root * BAR_STEPS[self.step % BAR_STEPS.len()];
+        let seconds = self.step_index as f32 / SAMPLE_RATE as f32;
+        let attack = (seconds / 0.005).min(1.);
+        let sample = Waveform::Triangle.sample(self.phase) * attack * (-seconds * 8.).exp();
+        self.phase = (self.phase + frequency / SAMPLE_RATE as f32).fract();
+        self.step_index += 1;
+        Some(sample * 0.3)
+    }
+}
+
+impl Source for MusicDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// How much sound effects are pitched up as the line speeds up, from the
+/// ratio of its speed to the curve's starting speed.
+pub fn pitch_for_speed(speed_ratio: f32) -> f32 {
+    speed_ratio.max(0.).powf(0.3).clamp(0.5, 2.)
+}
+
+pub fn tempo_for_speed(speed_ratio: f32) -> f32 {
+    (BASE_TEMPO * speed_ratio.max(0.).sqrt()).clamp(BASE_TEMPO * 0.8, MAX_TEMPO)
+}
+
+#[derive(Resource)]
+struct Sounds {
+    hit: Handle<Tone>,
+    miss: Handle<Tone>,
+    speed_up: Handle<Tone>,
+    game_over: Handle<Tone>,
+    music: Handle<MusicTrack>,
+}
+
+#[derive(Component)]
+struct Music;
+
+/// The line's speed, as a ratio to the starting speed, when the last
+/// speed-up sound played.
+#[derive(Resource)]
+struct AnnouncedSpeed(f32);
+
+impl Default for AnnouncedSpeed {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Plays generated sound effects for hits, misses, speed-ups and the end of a
+/// run, and a background track that speeds up with the line. Needs bevy's
+/// `AudioPlugin`, so it is left out in headless mode.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<SettingsPlugin>() {
+            app.add_plugins(SettingsPlugin);
+        }
+        app.add_audio_source::<Tone>()
+            .add_audio_source::<MusicTrack>()
+            .init_resource::<AnnouncedSpeed>()
+            .add_systems(Startup, create_sounds)
+            .add_systems(OnEnter(GameState::Countdown), reset_announced_speed)
+            .add_systems(
+                Update,
+                (
+                    play_hit_sounds,
+                    play_miss_sounds,
+                    play_speed_up_sounds.run_if(in_state(GameState::Playing)),
+                    follow_speed_with_music,
+                    apply_music_volume.run_if(resource_changed::<Settings>),
+                ),
+            )
+            .add_systems(OnEnter(GameState::GameOver), play_game_over_sound);
+    }
+}
+
+fn create_sounds(
+    mut commands: Commands,
+    mut tones: ResMut<Assets<Tone>>,
+    mut tracks: ResMut<Assets<MusicTrack>>,
+    settings: Res<Settings>,
+) {
+    let mut tone = |waveform, start_frequency, end_frequency, duration| {
+        tones.add(Tone {
+            waveform,
+            start_frequency,
+            end_frequency,
+            duration,
+        })
+    };
+    let sounds = Sounds {
+        hit: tone(Waveform::Sine, 660., 990., 0.1),
+        miss: tone(Waveform::Square, 220., 110., 0.2),
+        speed_up: tone(Waveform::Triangle, 440., 880., 0.25),
+        game_over: tone(Waveform::Sawtooth, 330., 82., 0.8),
+        music: tracks.add(MusicTrack::new(BASE_TEMPO)),
+    };
+    commands.spawn((
+        AudioPlayer(sounds.music.clone()),
+        PlaybackSettings::LOOP.with_volume(Volume::new(settings.volume * settings.music_volume)),
+        Music,
+    ));
+    commands.insert_resource(sounds);
+}
+
+/// The line's speed as a multiple of the speed runs start at, or 1 when no
+/// run is going.
+fn speed_ratio(
+    state: &State<GameState>,
+    line: &Query<&RotationSpeed>,
+    curve: &DifficultyCurve,
+) -> f32 {
+    let in_run = matches!(
+        state.get(),
+        GameState::Countdown | GameState::Playing | GameState::Paused
+    );
+    let start = curve.at(0).speed;
+    match line.iter().next() {
+        Some(speed) if in_run && start > 0. => speed.0.abs() / start,
+        _ => 1.,
+    }
+}
+
+fn play_effect(commands: &mut Commands, tone: &Handle<Tone>, settings: &Settings, pitch: f32) {
+    commands.spawn((
+        AudioPlayer(tone.clone()),
+        PlaybackSettings::DESPAWN
+            .with_volume(Volume::new(settings.volume * settings.sfx_volume))
+            .with_speed(pitch),
+    ));
+}
+
+fn play_hit_sounds(
+    mut commands: Commands,
+    mut hits: EventReader<TargetHit>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    state: Res<State<GameState>>,
+    line: Query<&RotationSpeed>,
+    curve: Res<DifficultyCurve>,
+) {
+    let pitch = pitch_for_speed(speed_ratio(&state, &line, &curve));
+    for hit in hits.read() {
+        // Better hits ring a little higher.
+        let grade_pitch = match hit.grade {
+            Grade::Perfect => 1.25,
+            Grade::Great => 1.12,
+            Grade::Good => 1.,
+        };
+        play_effect(&mut commands, &sounds.hit, &settings, pitch * grade_pitch);
+    }
+}
+
+fn play_miss_sounds(
+    mut commands: Commands,
+    mut missed: EventReader<Missed>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    state: Res<State<GameState>>,
+    line: Query<&RotationSpeed>,
+    curve: Res<DifficultyCurve>,
+) {
+    if missed.read().count() > 0 {
+        let pitch = pitch_for_speed(speed_ratio(&state, &line, &curve));
+        play_effect(&mut commands, &sounds.miss, &settings, pitch);
+    }
+}
+
+fn reset_announced_speed(mut announced: ResMut<AnnouncedSpeed>) {
+    announced.0 = 1.;
+}
+
+/// Plays a sound each time the line has sped up by another `SPEED_UP_STEP`
+/// since the last one, or since the run started.
+fn play_speed_up_sounds(
+    mut commands: Commands,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    state: Res<State<GameState>>,
+    line: Query<&RotationSpeed>,
+    curve: Res<DifficultyCurve>,
+    mut announced: ResMut<AnnouncedSpeed>,
+) {
+    let ratio = speed_ratio(&state, &line, &curve);
+    if ratio < announced.0 {
+        announced.0 = ratio;
+    } else if ratio > announced.0 && ratio >= announced.0 * SPEED_UP_STEP {
+        play_effect(
+            &mut commands,
+            &sounds.speed_up,
+            &settings,
+            pitch_for_speed(ratio),
+        );
+        announced.0 = ratio;
+    }
+}
+
+fn play_game_over_sound(mut commands: Commands, sounds: Res<Sounds>, settings: Res<Settings>) {
+    play_effect(&mut commands, &sounds.game_over, &settings, 1.);
+}
+
+fn follow_speed_with_music(
+    sounds: Res<Sounds>,
+    tracks: Res<Assets<MusicTrack>>,
+    state: Res<State<GameState>>,
+    line: Query<&RotationSpeed>,
+    curve: Res<DifficultyCurve>,
+) {
+    if let Some(track) = tracks.get(&sounds.music) {
+        track.set_tempo(tempo_for_speed(speed_ratio(&state, &line, &curve)));
+    }
+}
+
+fn apply_music_volume(settings: Res<Settings>, music: Query<&AudioSink, With<Music>>) {
+    for sink in music.iter() {
+        sink.set_volume(settings.volume * settings.music_volume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sounds_are_generated_and_follow_the_line_speed() {
+        let tone = Tone {
+            waveform: Waveform::Square,
+            start_frequency: 440.,
+            end_frequency: 220.,
+            duration: 0.5,
+        };
+        let samples: Vec<f32> = tone.decoder().collect();
+        assert_eq!(samples.len(), 22_050);
+        assert!(samples.iter().all(|sample| sample.abs() <= 1.));
+        // Faded out by the end, so it doesn't click.
+        assert!(samples[samples.len() - 1].abs() < 0.01);
+
+        assert_eq!(pitch_for_speed(1.), 1.);
+        assert!(pitch_for_speed(3.) > pitch_for_speed(2.));
+        assert!(tempo_for_speed(3.) > tempo_for_speed(1.));
+        assert_eq!(tempo_for_speed(100.), tempo_for_speed(1000.));
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{gameplay::Missed, grade::Grade};
+
+/// How the combo multiplier grows and what breaks the combo.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComboRules {
+    /// Consecutive hits needed for each step up in the multiplier.
+    pub hits_per_step: u32,
+    pub max_multiplier: u32,
+    /// Hits graded worse than this break the combo and score without the
+    /// multiplier.
+    pub lowest_grade: Grade,
+    /// Whether a miss that doesn't end the run, like losing a life, breaks
+    /// the combo.
+    pub break_on_miss: bool,
+}
+
+impl Default for ComboRules {
+    fn default() -> Self {
+        Self {
+            hits_per_step: 5,
+            max_multiplier: 4,
+            lowest_grade: Grade::Good,
+            break_on_miss: true,
+        }
+    }
+}
+
+impl ComboRules {
+    /// The multiplier for a hit that follows `combo` consecutive hits.
+    pub fn multiplier(&self, combo: u32) -> u32 {
+        (1 + combo / self.hits_per_step.max(1)).min(self.max_multiplier.max(1))
+    }
+
+    pub fn breaks(&self, grade: Grade) -> bool {
+        grade > self.lowest_grade
+    }
+}
+
+/// Consecutive hits in the current run.
+#[derive(Resource, Default)]
+pub struct Combo(pub u32);
+
+pub(crate) fn break_combo_on_miss(
+    mut missed: EventReader<Missed>,
+    rules: Res<ComboRules>,
+    mut combo: ResMut<Combo>,
+) {
+    if missed.read().count() > 0 && rules.break_on_miss {
+        combo.0 = 0;
+    }
+}
@@ -0,0 +1,307 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+/// Where the difficulty curve is loaded from, relative to the assets folder.
+/// With `None` nothing is loaded and the `DifficultyCurve` resource is used
+/// as inserted, which is what replays and tests do.
+#[derive(Resource, Clone)]
+pub struct DifficultyConfig {
+    pub path: Option<String>,
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> Self {
+        Self {
+            path: Some("default.difficulty.ron".to_string()),
+        }
+    }
+}
+
+/// Something extra that happens once a run reaches a keyframe.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Behaviour {
+    /// After each hit the line flips direction with this probability.
+    RandomReverse(f32),
+    /// Targets shrink by this many degrees per second until they are next
+    /// placed, down to the curve's minimum arc width.
+    Shrink(f32),
+    /// Targets move around the ring at this many degrees per second.
+    Drift(f32),
+    /// Targets swing up to `amplitude` degrees either side of where they were
+    /// placed, taking `period` seconds for a full swing.
+    Oscillate { amplitude: f32, period: f32 },
+    /// Targets move against the line at this fraction of its speed.
+    CounterRotate(f32),
+    /// Targets jump to a new random angle every this many seconds.
+    Teleport(f32),
+}
+
+/// Whether the targets of a round can be hit in any order, or must be hit in
+/// the order of their colours.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TargetOrder {
+    #[default]
+    AnyOrder,
+    InOrder,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyKeyframe {
+    /// Targets hit so far in the run. Hits count the same whatever their
+    /// grade or combo, so better timing doesn't make the game harder sooner.
+    #[serde(alias = "score")]
+    pub hits: u32,
+    /// Rotation speed of the line in radians per second.
+    pub speed: f32,
+    /// Full width of each target in degrees.
+    pub arc_width: f32,
+    #[serde(default = "one_target")]
+    pub targets: u32,
+    #[serde(default)]
+    pub order: TargetOrder,
+    #[serde(default)]
+    pub behaviours: Vec<Behaviour>,
+}
+
+fn one_target() -> u32 {
+    1
+}
+
+fn default_min_arc_width() -> f32 {
+    10.
+}
+
+/// Maps the number of hits in a run to difficulty. Speed and arc width are interpolated between
+/// keyframes, target count, order and behaviours come from the last keyframe
+/// reached.
+///
+/// The curve is loaded as an asset and copied into this resource, so edits to
+/// the file apply while the game runs.
+#[derive(Asset, TypePath, Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyCurve {
+    pub keyframes: Vec<DifficultyKeyframe>,
+    /// Targets never get narrower than this many degrees, however the
+    /// keyframes and behaviours shrink them.
+    #[serde(default = "default_min_arc_width")]
+    pub min_arc_width: f32,
+}
+
+impl Default for DifficultyCurve {
+    /// Speeds up by 0.5 per hit until 10, with one 50° target throughout.
+    fn default() -> Self {
+        Self {
+            keyframes: vec![
+                DifficultyKeyframe {
+                    hits: 0,
+                    speed: 1.5,
+                    arc_width: 50.,
+                    targets: 1,
+                    order: TargetOrder::AnyOrder,
+                    behaviours: Vec::new(),
+                },
+                DifficultyKeyframe {
+                    hits: 17,
+                    speed: 10.,
+                    arc_width: 50.,
+                    targets: 1,
+                    order: TargetOrder::AnyOrder,
+                    behaviours: Vec::new(),
+                },
+            ],
+            min_arc_width: default_min_arc_width(),
+        }
+    }
+}
+
+/// The difficulty after some number of hits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difficulty {
+    pub speed: f32,
+    /// Half-width of each target in radians.
+    pub target_half_width: f32,
+    pub targets: u32,
+    pub order: TargetOrder,
+    pub behaviours: Vec<Behaviour>,
+}
+
+impl DifficultyCurve {
+    pub fn at(&self, hits: u32) -> Difficulty {
+        let Some(last) = self.keyframes.last() else {
+            return Self::default().at(hits);
+        };
+        let next = self
+            .keyframes
+            .iter()
+            .position(|keyframe| keyframe.hits > hits);
+        let (from, to) = match next {
+            Some(0) => (&self.keyframes[0], &self.keyframes[0]),
+            Some(index) => (&self.keyframes[index - 1], &self.keyframes[index]),
+            None => (last, last),
+        };
+
+        let t = if to.hits > from.hits {
+            (hits - from.hits) as f32 / (to.hits - from.hits) as f32
+        } else {
+            0.
+        };
+        let arc_width = from.arc_width.lerp(to.arc_width, t).max(self.min_arc_width);
+        Difficulty {
+            speed: from.speed.lerp(to.speed, t),
+            target_half_width: (arc_width / 2.).to_radians(),
+            targets: from.targets,
+            order: from.order,
+            behaviours: from.behaviours.clone(),
+        }
+    }
+
+    /// Half-width in radians of the narrowest target allowed.
+    pub fn min_half_width(&self) -> f32 {
+        (self.min_arc_width / 2.).to_radians()
+    }
+
+    /// Sorts the keyframes by hits and rejects curves that can't be played.
+    pub fn validated(mut self) -> Result<Self, String> {
+        if self.keyframes.is_empty() {
+            return Err("Difficulty curve has no keyframes".to_string());
+        }
+        if self.min_arc_width.is_nan() || self.min_arc_width < 0. || self.min_arc_width >= 360. {
+            return Err("Minimum arc width is outside [0, 360)".to_string());
+        }
+        self.keyframes.sort_by_key(|keyframe| keyframe.hits);
+        for keyframe in &self.keyframes {
+            if keyframe.speed.is_nan() || keyframe.speed < 0. {
+                return Err(format!(
+                    "Keyframe at {} hits has a negative speed",
+                    keyframe.hits
+                ));
+            }
+            if keyframe.arc_width.is_nan() || keyframe.arc_width <= 0. || keyframe.arc_width >= 360.
+            {
+                return Err(format!(
+                    "Keyframe at {} hits has an arc width outside (0, 360)",
+                    keyframe.hits
+                ));
+            }
+            for behaviour in &keyframe.behaviours {
+                let valid = match *behaviour {
+                    Behaviour::Oscillate { period, .. } => period.is_finite() && period > 0.,
+                    Behaviour::Teleport(interval) => interval.is_finite() && interval > 0.,
+                    _ => true,
+                };
+                if !valid {
+                    return Err(format!(
+                        "Keyframe at {} hits has {behaviour:?}, which needs a positive time",
+                        keyframe.hits
+                    ));
+                }
+            }
+            if keyframe.targets == 0 {
+                return Err(format!("Keyframe at {} hits has no targets", keyframe.hits));
+            }
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Default)]
+struct DifficultyCurveLoader;
+
+impl AssetLoader for DifficultyCurveLoader {
+    type Asset = DifficultyCurve;
+    type Settings = ();
+    type Error = String;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<DifficultyCurve, String> {
+        let path = load_context.path().display().to_string();
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|err| format!("Could not read {path}: {err}"))?;
+        let curve: DifficultyCurve =
+            ron::de::from_bytes(&bytes).map_err(|err| format!("Could not parse {path}: {err}"))?;
+        curve.validated().map_err(|err| format!("{path}: {err}"))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["difficulty.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct DifficultyCurveHandle(Handle<DifficultyCurve>);
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DifficultyCurve>()
+            .init_asset_loader::<DifficultyCurveLoader>()
+            .init_resource::<DifficultyConfig>()
+            .init_resource::<DifficultyCurve>()
+            .add_systems(Startup, load_difficulty_curve)
+            .add_systems(
+                Update,
+                apply_difficulty_curve.run_if(resource_exists::<DifficultyCurveHandle>),
+            );
+    }
+}
+
+fn load_difficulty_curve(
+    mut commands: Commands,
+    config: Res<DifficultyConfig>,
+    asset_server: Res<AssetServer>,
+) {
+    if let Some(path) = &config.path {
+        commands.insert_resource(DifficultyCurveHandle(asset_server.load(path)));
+    }
+}
+
+fn apply_difficulty_curve(
+    mut events: EventReader<AssetEvent<DifficultyCurve>>,
+    handle: Res<DifficultyCurveHandle>,
+    curves: Res<Assets<DifficultyCurve>>,
+    mut curve: ResMut<DifficultyCurve>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+        if let Some(loaded) = curves.get(&handle.0) {
+            info!("Loaded difficulty curve");
+            *curve = loaded.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_curve_reaches_top_speed_after_17_hits() {
+        let curve: DifficultyCurve =
+            ron::de::from_str(include_str!("../assets/default.difficulty.ron")).unwrap();
+        let curve = curve.validated().unwrap();
+        assert!(curve.at(16).speed < 10.);
+        assert_eq!(curve.at(17).speed, 10.);
+        assert_eq!(curve.at(24).targets, 1);
+        assert_eq!(curve.at(25).targets, 2);
+        assert_eq!(curve.at(40).order, TargetOrder::InOrder);
+    }
+
+    #[test]
+    fn keyframes_saved_by_score_still_load() {
+        let keyframe: DifficultyKeyframe =
+            ron::de::from_str("(score: 5, speed: 1.0, arc_width: 40.0)").unwrap();
+        assert_eq!(keyframe.hits, 5);
+    }
+}
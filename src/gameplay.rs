@@ -0,0 +1,949 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    window::{PrimaryWindow, WindowFocused},
+};
+use rand::Rng;
+use std::f32::consts::{PI, TAU};
+
+use crate::{
+    action::{Action, ActionPlugin},
+    arc::{arcs_overlap, wrap_angle, RingAngle},
+    combo::{break_combo_on_miss, Combo, ComboRules},
+    difficulty::{Behaviour, DifficultyCurve, DifficultyPlugin, TargetOrder},
+    grade::Grade,
+    layout::{GameCamera, LayoutPlugin, LINE, LINE_HALF_WIDTH, RING, TARGET},
+    motion::{
+        counter_rotate_targets, drift_targets, oscillate_targets, teleport_targets,
+        CounterRotating, Drift, Oscillating, TeleportTimer,
+    },
+    rng::{GameRng, RngSeed},
+    settings::Theme,
+    state::{GameMode, GameState},
+};
+
+const COUNTDOWN_SECONDS: f32 = 3.;
+const INVULNERABLE_SECONDS: f32 = 1.;
+const RING_FLASHES_PER_SECOND: f32 = 8.;
+/// Random picks per target before placement gives up and spaces the targets
+/// evenly instead.
+const PLACEMENT_ATTEMPTS: usize = 100;
+/// Target colours in hit order, repeating if there are more targets.
+const TARGET_COLORS: [Color; 6] = [
+    Color::linear_rgba(1., 0., 0., 1.),
+    Color::linear_rgba(0., 0.3, 1., 1.),
+    Color::linear_rgba(1., 0.8, 0., 1.),
+    Color::linear_rgba(0.8, 0., 1., 1.),
+    Color::linear_rgba(0., 0.9, 0.9, 1.),
+    Color::linear_rgba(1., 0.4, 0., 1.),
+];
+
+#[derive(Resource, Default)]
+pub struct Score(pub u32);
+
+#[derive(Resource, Default)]
+pub struct BestScore(pub u32);
+
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub reversals: u32,
+    pub hits: u32,
+    pub perfect: u32,
+    pub great: u32,
+    pub good: u32,
+    pub max_combo: u32,
+}
+
+/// How many gameplay ticks run per second. Rotation, hit tests and speed
+/// changes all happen on these ticks, independent of the frame rate.
+#[derive(Resource, Clone, Copy)]
+pub struct TickRate(pub f64);
+
+impl Default for TickRate {
+    fn default() -> Self {
+        Self(120.)
+    }
+}
+
+/// Number of fixed gameplay ticks since the current run started.
+#[derive(Resource, Default)]
+pub struct GameTick(pub u64);
+
+/// Set when the player asked to reverse. Input is read once per frame before
+/// the fixed ticks run, so the press lands on the tick that was current when
+/// it was seen, even if the frame then has to catch up several ticks.
+#[derive(Resource, Default)]
+pub(crate) struct PendingReverse(pub bool);
+
+/// The targets currently on the ring. Once every one of them has been hit
+/// the round is cleared and new targets are placed.
+#[derive(Resource, Default)]
+pub(crate) struct TargetRound {
+    pub order: TargetOrder,
+    pub cleared: bool,
+}
+
+impl TargetRound {
+    /// Whether `target` can be hit now. `next_order` is the target to hit
+    /// next, from `next_target_order`.
+    fn can_be_hit(&self, target: &TargetZone, next_order: Option<u32>) -> bool {
+        !target.hit && (self.order == TargetOrder::AnyOrder || Some(target.order) == next_order)
+    }
+}
+
+/// How many lives a run in `GameMode::Lives` starts with.
+#[derive(Resource, Clone, Copy)]
+pub struct StartingLives(pub u32);
+
+impl Default for StartingLives {
+    fn default() -> Self {
+        Self(3)
+    }
+}
+
+/// When set, letting the line pass all the way through a target that could
+/// have been hit counts as a miss, as if the player had reversed outside it.
+#[derive(Resource, Default, Clone, Copy, PartialEq)]
+pub struct StrictMisses(pub bool);
+
+/// Lives left in the current run. Only present in `GameMode::Lives`; without
+/// it the first miss ends the run.
+#[derive(Resource)]
+pub struct Lives(pub u32);
+
+/// Misses don't cost a life until this runs out. The ring flashes meanwhile.
+#[derive(Resource)]
+pub struct Invulnerable(pub Timer);
+
+/// Sent when the line was reversed outside of every target that could be hit,
+/// or, with `StrictMisses`, when it passed one by.
+#[derive(Event)]
+pub struct Missed;
+
+/// Sent when the line was reversed inside a target.
+#[derive(Event)]
+pub struct TargetHit {
+    pub grade: Grade,
+    /// Where the line was, in radians around the ring.
+    pub angle: f32,
+}
+
+/// Whether the primary window has focus. Kept up to date in every state, so
+/// that focus lost during the countdown still pauses the run once it starts.
+#[derive(Resource, PartialEq)]
+pub struct WindowFocus(pub bool);
+
+impl Default for WindowFocus {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Counts down before each run starts.
+#[derive(Resource)]
+pub struct Countdown(pub Timer);
+
+impl Default for Countdown {
+    fn default() -> Self {
+        Self(Timer::from_seconds(COUNTDOWN_SECONDS, TimerMode::Once))
+    }
+}
+
+/// Everything spawned for a run, despawned when the next run starts or the
+/// game returns to the main menu.
+#[derive(Component)]
+struct GameplayEntity;
+
+#[derive(Component)]
+struct Ring;
+
+#[derive(Component)]
+pub struct RotationSpeed(pub f32);
+
+/// A target arc. Changing `half_width` rebuilds the mesh, and hit tests
+/// always use the current value.
+#[derive(Component)]
+pub struct TargetZone {
+    /// Half-width of the arc in radians.
+    pub half_width: f32,
+    /// Position in the round, which picks the colour and, for
+    /// `TargetOrder::InOrder`, when the target has to be hit.
+    pub order: u32,
+    /// Hit targets are hidden and no longer score until the next round.
+    pub hit: bool,
+}
+
+/// Makes a target narrower over time. The half-width shrinks by this many
+/// radians per second.
+#[derive(Component)]
+struct Shrinking(f32);
+
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<ActionPlugin>() {
+            app.add_plugins(ActionPlugin);
+        }
+        app.add_plugins((DifficultyPlugin, LayoutPlugin))
+            .init_state::<GameState>()
+            .init_resource::<GameMode>()
+            .init_resource::<Score>()
+            .init_resource::<BestScore>()
+            .init_resource::<RunStats>()
+            .init_resource::<TickRate>()
+            .init_resource::<GameTick>()
+            .init_resource::<PendingReverse>()
+            .init_resource::<TargetRound>()
+            .init_resource::<RngSeed>()
+            .init_resource::<Countdown>()
+            .init_resource::<WindowFocus>()
+            .init_resource::<StartingLives>()
+            .init_resource::<StrictMisses>()
+            .init_resource::<ComboRules>()
+            .init_resource::<Combo>()
+            .init_resource::<Theme>()
+            .add_event::<Missed>()
+            .add_event::<TargetHit>()
+            .add_systems(Startup, (apply_tick_rate, seed_rng))
+            .add_systems(PostStartup, spawn_camera)
+            .add_systems(
+                OnEnter(GameState::Countdown),
+                (
+                    despawn_gameplay_entities,
+                    start_run,
+                    setup,
+                    create_annulus_segment,
+                    create_rotating_line,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                tick_countdown.run_if(in_state(GameState::Countdown)),
+            )
+            .add_systems(
+                RunFixedMainLoop,
+                queue_reverse_input
+                    .in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    store_previous_angles,
+                    reverse_rotate_direction,
+                    score_hits,
+                    update_best_score,
+                    rotate_line,
+                    (
+                        drift_targets,
+                        oscillate_targets,
+                        counter_rotate_targets,
+                        teleport_targets,
+                    ),
+                    detect_passed_targets.run_if(resource_equals(StrictMisses(true))),
+                    break_combo_on_miss,
+                    resolve_misses,
+                    shrink_targets,
+                    place_targets,
+                    update_line_speed,
+                    tick_invulnerability,
+                    advance_tick,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    track_window_focus,
+                    (interpolate_ring_transforms, flash_ring, pause_game)
+                        .run_if(in_state(GameState::Playing)),
+                )
+                    .chain(),
+            )
+            .add_systems(Update, (update_target_meshes, update_target_colors).chain())
+            .add_systems(Update, apply_theme.run_if(resource_changed::<Theme>))
+            .add_systems(OnEnter(GameState::Paused), (pause_game_clock, show_ring))
+            .add_systems(OnExit(GameState::Paused), resume_game_clock)
+            .add_systems(OnEnter(GameState::MainMenu), despawn_gameplay_entities);
+    }
+}
+
+fn apply_tick_rate(tick_rate: Res<TickRate>, mut time: ResMut<Time<Fixed>>) {
+    time.set_timestep_hz(tick_rate.0);
+}
+
+fn store_previous_angles(mut query: Query<&mut RingAngle>) {
+    for mut angle in query.iter_mut() {
+        angle.previous = angle.current;
+    }
+}
+
+fn rotate_line(time: Res<Time>, mut query: Query<(&RotationSpeed, &mut RingAngle)>) {
+    for (rotation_speed, mut angle) in query.iter_mut() {
+        angle.current -= rotation_speed.0 * time.delta_secs();
+    }
+}
+
+fn interpolate_ring_transforms(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(&RingAngle, &mut Transform)>,
+) {
+    let alpha = time.overstep_fraction();
+    for (angle, mut transform) in query.iter_mut() {
+        transform.rotation = Quat::from_rotation_z(angle.interpolated(alpha));
+    }
+}
+
+fn queue_reverse_input(
+    actions: Res<ButtonInput<Action>>,
+    mut pending_reverse: ResMut<PendingReverse>,
+) {
+    if actions.just_pressed(Action::Reverse) {
+        pending_reverse.0 = true;
+    }
+}
+
+fn track_window_focus(
+    mut focus_events: EventReader<WindowFocused>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut focus: ResMut<WindowFocus>,
+) {
+    let Ok(primary) = window.get_single() else {
+        focus_events.clear();
+        return;
+    };
+    if let Some(event) = focus_events
+        .read()
+        .filter(|event| event.window == primary)
+        .last()
+    {
+        focus.set_if_neq(WindowFocus(event.focused));
+    }
+}
+
+/// Pauses on the pause action, or when focus was lost since this last ran,
+/// which includes during the countdown before the run.
+fn pause_game(
+    actions: Res<ButtonInput<Action>>,
+    focus: Res<WindowFocus>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let lost_focus = focus.is_changed() && !focus.0;
+    if actions.just_pressed(Action::Pause) || lost_focus {
+        next_state.set(GameState::Paused);
+    }
+}
+
+fn pause_game_clock(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_game_clock(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+pub(crate) fn reverse_rotate_direction(
+    mut query: Query<(&mut RotationSpeed, &RingAngle), Without<TargetZone>>,
+    mut targets: Query<(&mut TargetZone, &RingAngle, &mut Visibility)>,
+    mut pending_reverse: ResMut<PendingReverse>,
+    mut run_stats: ResMut<RunStats>,
+    mut round: ResMut<TargetRound>,
+    mut missed: EventWriter<Missed>,
+    mut hits: EventWriter<TargetHit>,
+) {
+    if std::mem::take(&mut pending_reverse.0) {
+        for (mut rotation_speed, line_angle) in query.iter_mut() {
+            rotation_speed.0 *= -1.;
+            run_stats.reversals += 1;
+            let next_order = next_target_order(targets.iter().map(|(target, _, _)| target));
+            let hit_target = targets.iter_mut().find(|(target, target_angle, _)| {
+                round.can_be_hit(target, next_order)
+                    && arcs_overlap(
+                        line_angle.current,
+                        LINE_HALF_WIDTH,
+                        target_angle.current,
+                        target.half_width,
+                    )
+            });
+            if let Some((mut target, target_angle, mut visibility)) = hit_target {
+                target.hit = true;
+                *visibility = Visibility::Hidden;
+                let offset = wrap_angle(line_angle.current - target_angle.current).abs()
+                    / (target.half_width + LINE_HALF_WIDTH);
+                hits.send(TargetHit {
+                    grade: Grade::from_offset(offset),
+                    angle: line_angle.current,
+                });
+            } else {
+                missed.send(Missed);
+            }
+        }
+        round.cleared = targets.iter().all(|(target, _, _)| target.hit);
+    }
+}
+
+/// Awards each hit its grade's points times the combo multiplier.
+fn score_hits(
+    mut hits: EventReader<TargetHit>,
+    mut score: ResMut<Score>,
+    mut run_stats: ResMut<RunStats>,
+    mut combo: ResMut<Combo>,
+    rules: Res<ComboRules>,
+) {
+    for hit in hits.read() {
+        if rules.breaks(hit.grade) {
+            score.0 += hit.grade.points();
+            combo.0 = 0;
+        } else {
+            score.0 += hit.grade.points() * rules.multiplier(combo.0);
+            combo.0 += 1;
+        }
+        run_stats.max_combo = run_stats.max_combo.max(combo.0);
+        run_stats.hits += 1;
+        match hit.grade {
+            Grade::Perfect => run_stats.perfect += 1,
+            Grade::Great => run_stats.great += 1,
+            Grade::Good => run_stats.good += 1,
+        }
+    }
+}
+
+/// Sends `Missed` for every target that could have been hit and that the line
+/// was over on the previous tick but has now left entirely. Both angles are
+/// compared before and after the tick, so targets that were teleported or
+/// placed this tick are never passed.
+fn detect_passed_targets(
+    line: Query<&RingAngle, With<RotationSpeed>>,
+    targets: Query<(&TargetZone, &RingAngle)>,
+    round: Res<TargetRound>,
+    mut missed: EventWriter<Missed>,
+) {
+    let Ok(line_angle) = line.get_single() else {
+        return;
+    };
+    let next_order = next_target_order(targets.iter().map(|(target, _)| target));
+    for (target, target_angle) in targets.iter() {
+        if !round.can_be_hit(target, next_order) {
+            continue;
+        }
+        let was_over = arcs_overlap(
+            line_angle.previous,
+            LINE_HALF_WIDTH,
+            target_angle.previous,
+            target.half_width,
+        );
+        let is_over = arcs_overlap(
+            line_angle.current,
+            LINE_HALF_WIDTH,
+            target_angle.current,
+            target.half_width,
+        );
+        if was_over && !is_over {
+            missed.send(Missed);
+        }
+    }
+}
+
+/// A miss costs a life, or ends the run when there are none left.
+fn resolve_misses(
+    mut commands: Commands,
+    mut missed: EventReader<Missed>,
+    lives: Option<ResMut<Lives>>,
+    invulnerable: Option<Res<Invulnerable>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if missed.read().count() == 0 || invulnerable.is_some() {
+        return;
+    }
+    match lives {
+        Some(mut lives) if lives.0 > 1 => {
+            lives.0 -= 1;
+            commands.insert_resource(Invulnerable(Timer::from_seconds(
+                INVULNERABLE_SECONDS,
+                TimerMode::Once,
+            )));
+        }
+        Some(mut lives) => {
+            lives.0 = 0;
+            next_state.set(GameState::GameOver);
+        }
+        None => next_state.set(GameState::GameOver),
+    }
+}
+
+fn tick_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    invulnerable: Option<ResMut<Invulnerable>>,
+) {
+    if let Some(mut invulnerable) = invulnerable {
+        if invulnerable.0.tick(time.delta()).finished() {
+            commands.remove_resource::<Invulnerable>();
+        }
+    }
+}
+
+/// Recolours the ring and line of a run already in progress.
+fn apply_theme(
+    theme: Res<Theme>,
+    ring: Query<&MeshMaterial2d<ColorMaterial>, With<Ring>>,
+    line: Query<&MeshMaterial2d<ColorMaterial>, With<RotationSpeed>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for material in ring.iter() {
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.color = theme.ring();
+        }
+    }
+    for material in line.iter() {
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.color = theme.line();
+        }
+    }
+}
+
+fn flash_ring(
+    invulnerable: Option<Res<Invulnerable>>,
+    mut query: Query<&mut Visibility, With<Ring>>,
+) {
+    let visible = invulnerable.is_none_or(|invulnerable| {
+        (invulnerable.0.elapsed_secs() * RING_FLASHES_PER_SECOND).fract() < 0.5
+    });
+    let visibility = if visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut ring_visibility in query.iter_mut() {
+        ring_visibility.set_if_neq(visibility);
+    }
+}
+
+/// The flashing stops while paused, so the ring is shown rather than left
+/// hidden behind the pause menu until the run resumes.
+fn show_ring(mut query: Query<&mut Visibility, With<Ring>>) {
+    for mut visibility in query.iter_mut() {
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
+
+fn update_best_score(score: Res<Score>, mut best_score: ResMut<BestScore>) {
+    if score.is_changed() {
+        best_score.0 = best_score.0.max(score.0);
+    }
+}
+
+/// Starts a new round once the last one is cleared: every target moves to a
+/// new random angle, and targets are spawned, despawned and resized to match
+/// the difficulty curve.
+fn place_targets(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut TargetZone, &mut RingAngle, &mut Visibility)>,
+    mut round: ResMut<TargetRound>,
+    run_stats: Res<RunStats>,
+    curve: Res<DifficultyCurve>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    if !round.cleared {
+        return;
+    };
+    let difficulty = curve.at(run_stats.hits);
+    round.cleared = false;
+    round.order = difficulty.order;
+
+    // Keep the line from touching two targets at once.
+    let angles = pick_target_angles(
+        game_rng.rng(),
+        difficulty.targets as usize,
+        difficulty.target_half_width + LINE_HALF_WIDTH,
+    );
+    let mut targets: Vec<_> = query.iter_mut().collect();
+    targets.sort_by_key(|(_, target, _, _)| target.order);
+    for (entity, ..) in targets.iter().skip(angles.len()) {
+        commands.entity(*entity).despawn_recursive();
+    }
+
+    for (order, angle) in angles.into_iter().enumerate() {
+        let entity = match targets.get_mut(order) {
+            Some((entity, target, ring_angle, visibility)) => {
+                ring_angle.teleport(angle);
+                target.half_width = difficulty.target_half_width;
+                target.hit = false;
+                **visibility = Visibility::Inherited;
+                *entity
+            }
+            None => spawn_target(
+                &mut commands,
+                difficulty.target_half_width,
+                order as u32,
+                angle,
+            ),
+        };
+        insert_target_behaviours(&mut commands.entity(entity), &difficulty.behaviours);
+    }
+}
+
+/// Replaces a target's behaviour components with the ones for `behaviours`.
+fn insert_target_behaviours(entity: &mut EntityCommands, behaviours: &[Behaviour]) {
+    entity.remove::<(
+        Shrinking,
+        Drift,
+        Oscillating,
+        CounterRotating,
+        TeleportTimer,
+    )>();
+    for behaviour in behaviours {
+        match *behaviour {
+            Behaviour::RandomReverse(_) => {}
+            Behaviour::Shrink(degrees_per_second) => {
+                entity.insert(Shrinking((degrees_per_second / 2.).to_radians()));
+            }
+            Behaviour::Drift(degrees_per_second) => {
+                entity.insert(Drift(degrees_per_second.to_radians()));
+            }
+            Behaviour::Oscillate { amplitude, period } => {
+                entity.insert(Oscillating::new(amplitude.to_radians(), period));
+            }
+            Behaviour::CounterRotate(factor) => {
+                entity.insert(CounterRotating(factor));
+            }
+            Behaviour::Teleport(interval) => {
+                entity.insert(TeleportTimer(Timer::from_seconds(
+                    interval,
+                    TimerMode::Repeating,
+                )));
+            }
+        }
+    }
+}
+
+/// Picks random angles for `count` targets whose arcs, each `half_width`
+/// wide on either side, don't overlap. If random picks keep colliding the
+/// targets are spaced evenly from a random start instead.
+fn pick_target_angles(rng: &mut impl Rng, count: usize, half_width: f32) -> Vec<f32> {
+    let mut occupied = Vec::with_capacity(count);
+    for _ in 0..count {
+        match pick_free_angle(rng, half_width, &occupied) {
+            Some(angle) => occupied.push((angle, half_width)),
+            None => {
+                let start = rng.gen_range(0.0..2.0 * PI);
+                return (0..count)
+                    .map(|i| start + i as f32 * TAU / count as f32)
+                    .collect();
+            }
+        }
+    }
+    occupied.into_iter().map(|(angle, _)| angle).collect()
+}
+
+/// Picks a random angle for an arc of `half_width` that doesn't overlap any
+/// of the `occupied` arcs, given as centre and half-width. Gives up after
+/// `PLACEMENT_ATTEMPTS` tries.
+pub(crate) fn pick_free_angle(
+    rng: &mut impl Rng,
+    half_width: f32,
+    occupied: &[(f32, f32)],
+) -> Option<f32> {
+    (0..PLACEMENT_ATTEMPTS)
+        .map(|_| rng.gen_range(0.0..2.0 * PI))
+        .find(|angle| {
+            occupied.iter().all(|(other, other_half_width)| {
+                !arcs_overlap(*angle, half_width, *other, *other_half_width)
+            })
+        })
+}
+
+fn shrink_targets(
+    time: Res<Time>,
+    curve: Res<DifficultyCurve>,
+    mut query: Query<(&Shrinking, &mut TargetZone)>,
+) {
+    let min_half_width = curve.min_half_width();
+    for (Shrinking(rate), mut target) in query.iter_mut() {
+        if target.half_width > min_half_width {
+            target.half_width = (target.half_width - rate * time.delta_secs()).max(min_half_width);
+        }
+    }
+}
+
+/// Builds the mesh of newly spawned targets and rebuilds it when a target's
+/// width changes.
+fn update_target_meshes(
+    mut commands: Commands,
+    query: Query<(Entity, &TargetZone, Option<&Mesh2d>), Changed<TargetZone>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, target, mesh) in query.iter() {
+        match mesh {
+            Some(mesh) => {
+                meshes.insert(&mesh.0, target_mesh(target.half_width));
+            }
+            None => {
+                commands.entity(entity).insert((
+                    Mesh2d(meshes.add(target_mesh(target.half_width))),
+                    MeshMaterial2d(materials.add(target_color(target.order))),
+                ));
+            }
+        }
+    }
+}
+
+/// The `order` of the target to hit next with `TargetOrder::InOrder`: the
+/// lowest of the targets not hit yet.
+fn next_target_order<'a>(targets: impl IntoIterator<Item = &'a TargetZone>) -> Option<u32> {
+    targets
+        .into_iter()
+        .filter(|target| !target.hit)
+        .map(|target| target.order)
+        .min()
+}
+
+/// When targets have to be hit in order, dims every target but the next one.
+fn update_target_colors(
+    changed: Query<(), Changed<TargetZone>>,
+    targets: Query<(&TargetZone, &MeshMaterial2d<ColorMaterial>)>,
+    round: Res<TargetRound>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    let next_order = next_target_order(targets.iter().map(|(target, _)| target));
+    for (target, material) in targets.iter() {
+        let mut color = target_color(target.order);
+        // Hit targets are hidden, so only unhit ones are ever seen dimmed.
+        if !round.can_be_hit(target, next_order) {
+            color = color.darker(0.3);
+        }
+        if let Some(material) = materials.get_mut(&material.0) {
+            if material.color != color {
+                material.color = color;
+            }
+        }
+    }
+}
+
+fn target_color(order: u32) -> Color {
+    TARGET_COLORS[order as usize % TARGET_COLORS.len()]
+}
+
+/// Sets the line's speed from the difficulty curve after every hit, keeping
+/// its direction.
+fn update_line_speed(
+    mut query: Query<&mut RotationSpeed>,
+    mut hits: EventReader<TargetHit>,
+    run_stats: Res<RunStats>,
+    curve: Res<DifficultyCurve>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    if hits.read().count() == 0 {
+        return;
+    };
+    let difficulty = curve.at(run_stats.hits);
+    for mut rotation_speed in query.iter_mut() {
+        let mut speed = difficulty.speed.copysign(rotation_speed.0);
+        for behaviour in &difficulty.behaviours {
+            if let Behaviour::RandomReverse(chance) = *behaviour {
+                if game_rng.rng().gen_bool(chance.clamp(0., 1.) as f64) {
+                    speed = -speed;
+                }
+            }
+        }
+        rotation_speed.0 = speed;
+    }
+}
+
+fn advance_tick(mut tick: ResMut<GameTick>) {
+    tick.0 += 1;
+}
+
+fn seed_rng(mut commands: Commands, rng_seed: Res<RngSeed>) {
+    commands.insert_resource(GameRng::from_config(*rng_seed));
+}
+
+/// Spawns the game's camera, unless the host app tagged one of its own.
+fn spawn_camera(mut commands: Commands, cameras: Query<(), With<GameCamera>>) {
+    if cameras.is_empty() {
+        commands.spawn((Camera2d, GameCamera));
+    }
+}
+
+fn despawn_gameplay_entities(mut commands: Commands, query: Query<Entity, With<GameplayEntity>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn start_run(
+    mut commands: Commands,
+    rng_seed: Res<RngSeed>,
+    mode: Res<GameMode>,
+    starting_lives: Res<StartingLives>,
+) {
+    // Starting cleared makes place_targets pick the first target angles on the
+    // first tick of the run.
+    commands.insert_resource(Score::default());
+    commands.insert_resource(RunStats::default());
+    commands.insert_resource(Combo::default());
+    commands.insert_resource(GameTick::default());
+    commands.insert_resource(PendingReverse::default());
+    commands.insert_resource(TargetRound {
+        cleared: true,
+        ..default()
+    });
+    commands.insert_resource(GameRng::from_config(*rng_seed));
+    commands.insert_resource(Countdown::default());
+    commands.remove_resource::<Invulnerable>();
+    match *mode {
+        GameMode::Lives => commands.insert_resource(Lives(starting_lives.0)),
+        GameMode::Classic => commands.remove_resource::<Lives>(),
+    }
+}
+
+fn tick_countdown(
+    time: Res<Time>,
+    mut countdown: ResMut<Countdown>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if countdown.0.tick(time.delta()).just_finished() {
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    theme: Res<Theme>,
+) {
+    let background_circle = Annulus::new(RING.inner, RING.outer);
+    let color = theme.ring();
+
+    commands.spawn((
+        Mesh2d(meshes.add(background_circle)),
+        MeshMaterial2d(materials.add(color)),
+        Transform::default(),
+        Ring,
+        GameplayEntity,
+    ));
+}
+
+fn create_rotating_line(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    curve: Res<DifficultyCurve>,
+    theme: Res<Theme>,
+) {
+    let mut line = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    let color = theme.line();
+
+    let mut vertices = vec![];
+    for i in 0..=1 {
+        let angle = if i == 1 {
+            LINE_HALF_WIDTH
+        } else {
+            -LINE_HALF_WIDTH
+        };
+        vertices.push([
+            ops::sin(angle) * LINE.inner,
+            ops::cos(angle) * LINE.inner,
+            0.,
+        ]);
+        vertices.push([
+            ops::sin(angle) * LINE.outer,
+            ops::cos(angle) * LINE.outer,
+            0.,
+        ]);
+    }
+    line.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+
+    let indices = vec![0, 2, 1, 2, 3, 1];
+    line.insert_indices(Indices::U32(indices));
+
+    commands.spawn((
+        Mesh2d(meshes.add(line)),
+        MeshMaterial2d(materials.add(color)),
+        Transform::from_xyz(0., 0., 2.),
+        RingAngle::default(),
+        RotationSpeed(curve.at(0).speed),
+        GameplayEntity,
+    ));
+}
+
+fn create_annulus_segment(mut commands: Commands, curve: Res<DifficultyCurve>) {
+    // Shown spaced out from the top during the countdown, then moved to random
+    // angles by place_targets on the first tick.
+    let difficulty = curve.at(0);
+    for order in 0..difficulty.targets {
+        let angle = order as f32 * TAU / difficulty.targets as f32;
+        spawn_target(&mut commands, difficulty.target_half_width, order, angle);
+    }
+}
+
+/// Spawns a target without a mesh; update_target_meshes adds one.
+fn spawn_target(commands: &mut Commands, half_width: f32, order: u32, angle: f32) -> Entity {
+    commands
+        .spawn((
+            Transform {
+                translation: Vec3::new(0., 0., 1.),
+                rotation: Quat::from_rotation_z(angle),
+                ..default()
+            },
+            Visibility::default(),
+            RingAngle::new(angle),
+            TargetZone {
+                half_width,
+                order,
+                hit: false,
+            },
+            GameplayEntity,
+        ))
+        .id()
+}
+
+fn target_mesh(half_width: f32) -> Mesh {
+    let mut segment = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    let resolution = 5;
+
+    let start_angle = -half_width;
+    let end_angle = half_width;
+    let angle_increment = (end_angle - start_angle) / resolution as f32;
+
+    let mut vertices = vec![];
+    for i in 0..=resolution {
+        let angle = start_angle + i as f32 * angle_increment;
+        vertices.push([
+            ops::sin(angle) * TARGET.inner,
+            ops::cos(angle) * TARGET.inner,
+            0.,
+        ]);
+        vertices.push([
+            ops::sin(angle) * TARGET.outer,
+            ops::cos(angle) * TARGET.outer,
+            0.,
+        ]);
+    }
+
+    segment.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+
+    let mut indices = vec![];
+    for i in (0..resolution * 2 - 1).step_by(2) {
+        indices.extend_from_slice(&[i, i + 2, i + 1]);
+        indices.extend_from_slice(&[i + 1, i + 2, i + 3]);
+    }
+    // indices.extend_from_slice(&[0, 2, 1]);
+    segment.insert_indices(Indices::U32(indices));
+    segment
+}
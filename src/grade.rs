@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// How close to the centre of a target the line was when it was reversed.
+/// Better grades compare as lower.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Grade {
+    Perfect,
+    Great,
+    Good,
+}
+
+impl Grade {
+    /// Grades a hit by its distance from the target's centre, as a fraction
+    /// of the furthest distance that still counts as a hit.
+    pub fn from_offset(offset: f32) -> Self {
+        if offset <= 0.25 {
+            Grade::Perfect
+        } else if offset <= 0.6 {
+            Grade::Great
+        } else {
+            Grade::Good
+        }
+    }
+
+    pub fn points(self) -> u32 {
+        match self {
+            Grade::Perfect => 3,
+            Grade::Great => 2,
+            Grade::Good => 1,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Grade::Perfect => "Perfect",
+            Grade::Great => "Great",
+            Grade::Good => "Good",
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use bevy::{
+    asset::AssetPlugin,
+    input::keyboard::KeyboardInput,
+    prelude::*,
+    state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+    window::{WindowFocused, WindowMoved, WindowResized},
+};
+use std::time::Duration;
+
+use crate::{
+    action::BindingsConfig, difficulty::DifficultyConfig, gameplay::TickRate,
+    settings::SettingsConfig,
+};
+
+/// Gameplay ticks per second in headless mode. Every `App::update` advances
+/// time by exactly one tick.
+pub const HEADLESS_TICK_RATE: f64 = 60.;
+
+/// Stands in for `DefaultPlugins` when there is no window or GPU, e.g. in
+/// tests. Input is driven by writing to `ButtonInput<KeyCode>` or
+/// `ButtonInput<MouseButton>` directly, and focus changes by spawning an
+/// entity with `PrimaryWindow` and sending `WindowFocused` events for it. The
+/// difficulty curve, controls and settings files are not loaded, so runs use
+/// whatever `DifficultyCurve` is inserted, the default bindings and the
+/// default settings.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        // Computed the same way Time<Fixed> turns a tick rate into a timestep,
+        // so that the two always agree.
+        let frame_time = Duration::from_secs_f64(1. / HEADLESS_TICK_RATE);
+        app.add_plugins((MinimalPlugins, StatesPlugin, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<Touches>()
+            .add_event::<KeyboardInput>()
+            .add_event::<WindowFocused>()
+            .add_event::<WindowResized>()
+            .add_event::<WindowMoved>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(frame_time))
+            .insert_resource(TickRate(HEADLESS_TICK_RATE))
+            .insert_resource(DifficultyConfig { path: None })
+            .insert_resource(BindingsConfig { path: None })
+            .insert_resource(SettingsConfig {
+                path: None,
+                manage_window: false,
+            });
+    }
+}
@@ -0,0 +1,346 @@
+use bevy::{
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, fs, path::PathBuf};
+
+use crate::{
+    action::Action,
+    gameplay::Score,
+    replay::ReplayPlayback,
+    rng::GameRng,
+    ron_file::{read_ron, write_ron},
+    state::{GameMode, GameState},
+};
+
+const MAX_NAME_LENGTH: usize = 12;
+
+/// Where the high-score table lives and how many entries it keeps.
+/// Insert this before adding the plugin to override the defaults.
+#[derive(Resource, Clone)]
+pub struct HighScoreConfig {
+    pub path: PathBuf,
+    pub capacity: usize,
+}
+
+impl Default for HighScoreConfig {
+    fn default() -> Self {
+        let path = dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("SpinnyLock")
+            .join("highscores.ron");
+        Self { path, capacity: 10 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: u32,
+    pub date: String,
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    pub fn qualifies(&self, score: u32, capacity: usize) -> bool {
+        score > 0
+            && (self.entries.len() < capacity
+                || self.entries.iter().any(|entry| entry.score < score))
+    }
+
+    /// Inserts the entry in score order and returns its rank, or `None` if it
+    /// did not make the table.
+    pub fn insert(&mut self, entry: HighScoreEntry, capacity: usize) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|existing| existing.score < entry.score)
+            .unwrap_or(self.entries.len());
+        if rank >= capacity {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(capacity);
+        Some(rank)
+    }
+
+    pub fn load(config: &HighScoreConfig) -> Self {
+        match read_ron::<HighScores>(&config.path) {
+            Ok(Some(mut high_scores)) => {
+                high_scores
+                    .entries
+                    .sort_by_key(|entry| Reverse(entry.score));
+                high_scores.entries.truncate(config.capacity);
+                high_scores
+            }
+            Ok(None) => Self::default(),
+            Err(err) => {
+                warn!("Starting a new high-score table: {err}");
+                let backup = config.path.with_extension("ron.bak");
+                if let Err(err) = fs::rename(&config.path, &backup) {
+                    warn!("Could not back up corrupt high scores: {err}");
+                }
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, config: &HighScoreConfig) {
+        if let Err(err) = write_ron(&config.path, self) {
+            warn!("High scores not saved: {err}");
+        }
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct PendingHighScore {
+    entry: HighScoreEntry,
+    /// Whether a key typed into the name this frame. Such a key doesn't also
+    /// submit the name, even if it is bound to `Action::Confirm`.
+    typed: bool,
+}
+
+#[derive(Component)]
+struct NameEntryPrompt;
+
+#[derive(Component)]
+struct NameEntryText;
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HighScoreConfig>()
+            .init_resource::<HighScores>()
+            .add_systems(Startup, load_high_scores)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                check_for_new_record.run_if(not(resource_exists::<ReplayPlayback>)),
+            )
+            .add_systems(
+                Update,
+                (type_name, submit_name)
+                    .chain()
+                    .run_if(in_state(GameState::GameOver))
+                    .run_if(resource_exists::<PendingHighScore>),
+            )
+            .add_systems(OnExit(GameState::GameOver), cancel_name_entry);
+    }
+}
+
+fn load_high_scores(mut high_scores: ResMut<HighScores>, config: Res<HighScoreConfig>) {
+    *high_scores = HighScores::load(&config);
+}
+
+fn check_for_new_record(
+    mut commands: Commands,
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    game_rng: Res<GameRng>,
+    high_scores: Res<HighScores>,
+    config: Res<HighScoreConfig>,
+) {
+    if !high_scores.qualifies(score.0, config.capacity) {
+        return;
+    }
+    commands.insert_resource(PendingHighScore {
+        entry: HighScoreEntry {
+            name: String::new(),
+            score: score.0,
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            mode: *mode,
+            seed: Some(game_rng.seed()),
+        },
+        typed: false,
+    });
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            NameEntryPrompt,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("New high score! Enter your name:"),
+                TextFont {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                Text::new("Enter to save, Esc to skip"),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                Text::new("_"),
+                TextFont {
+                    font_size: 40.0,
+                    ..default()
+                },
+                NameEntryText,
+            ));
+        });
+}
+
+fn type_name(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut pending: ResMut<PendingHighScore>,
+    mut name_text: Query<&mut Text, With<NameEntryText>>,
+) {
+    let PendingHighScore { entry, typed } = &mut *pending;
+    let name = &mut entry.name;
+    *typed = false;
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(characters) => {
+                *typed = true;
+                for character in characters.chars() {
+                    if (character.is_alphanumeric() || character == '-' || character == '_')
+                        && name.chars().count() < MAX_NAME_LENGTH
+                    {
+                        name.push(character);
+                    }
+                }
+            }
+            // Spaces can separate words, but not start the name.
+            Key::Space => {
+                *typed = true;
+                if !name.is_empty() && name.chars().count() < MAX_NAME_LENGTH {
+                    name.push(' ');
+                }
+            }
+            Key::Backspace => {
+                name.pop();
+            }
+            _ => {}
+        }
+    }
+
+    for mut text in name_text.iter_mut() {
+        **text = format!("{}_", name);
+    }
+}
+
+/// Saves the name on confirm, or closes the prompt without saving on back.
+fn submit_name(
+    mut commands: Commands,
+    mut actions: ResMut<ButtonInput<Action>>,
+    pending: Res<PendingHighScore>,
+    mut high_scores: ResMut<HighScores>,
+    prompt: Query<Entity, With<NameEntryPrompt>>,
+    config: Res<HighScoreConfig>,
+) {
+    // Consumed so that the same press doesn't also leave the game over screen.
+    if actions.clear_just_pressed(Action::Back) {
+        cancel_name_entry(commands, prompt);
+        return;
+    }
+    if !actions.clear_just_pressed(Action::Confirm) || pending.typed {
+        return;
+    }
+    let mut entry = pending.entry.clone();
+    entry.name = entry.name.trim_end().to_string();
+    if entry.name.is_empty() {
+        entry.name = "Player".to_string();
+    }
+    if high_scores.insert(entry, config.capacity).is_some() {
+        high_scores.save(&config);
+    }
+    commands.remove_resource::<PendingHighScore>();
+    for entity in prompt.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn cancel_name_entry(mut commands: Commands, prompt: Query<Entity, With<NameEntryPrompt>>) {
+    commands.remove_resource::<PendingHighScore>();
+    for entity in prompt.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn high_score(name: &str, score: u32) -> HighScoreEntry {
+        HighScoreEntry {
+            name: name.to_string(),
+            score,
+            date: "2024-01-01".to_string(),
+            mode: GameMode::Classic,
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn high_scores_are_ranked_and_kept_to_capacity() {
+        let mut high_scores = HighScores::default();
+        assert!(!high_scores.qualifies(0, 3));
+        assert_eq!(high_scores.insert(high_score("a", 5), 3), Some(0));
+        assert_eq!(high_scores.insert(high_score("b", 9), 3), Some(0));
+        // Ties rank below the score that was already there.
+        assert_eq!(high_scores.insert(high_score("c", 5), 3), Some(2));
+        assert!(!high_scores.qualifies(5, 3));
+        assert!(high_scores.qualifies(6, 3));
+        assert_eq!(high_scores.insert(high_score("d", 4), 3), None);
+        assert_eq!(high_scores.insert(high_score("e", 7), 3), Some(1));
+
+        let names: Vec<&str> = high_scores
+            .entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, ["b", "e", "a"]);
+    }
+
+    #[test]
+    fn high_scores_survive_a_round_trip_and_corrupt_files_are_backed_up() {
+        let dir =
+            std::env::temp_dir().join(format!("spinny-lock-high-scores-{}", std::process::id()));
+        let config = HighScoreConfig {
+            path: dir.join("highscores.ron"),
+            capacity: 2,
+        };
+        let mut high_scores = HighScores::default();
+        high_scores.insert(high_score("a", 3), 5);
+        high_scores.insert(high_score("b", 8), 5);
+        high_scores.insert(high_score("c", 5), 5);
+        high_scores.save(&config);
+        // Loading sorts and trims to the configured capacity.
+        let loaded = HighScores::load(&config);
+
+        fs::write(&config.path, "not a high score table").unwrap();
+        let from_corrupt = HighScores::load(&config);
+        let backup = fs::read_to_string(dir.join("highscores.ron.bak"));
+        let original_remains = config.path.exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.entries, high_scores.entries[..2]);
+        assert!(from_corrupt.entries.is_empty());
+        assert_eq!(backup.unwrap(), "not a high score table");
+        assert!(!original_remains);
+    }
+}
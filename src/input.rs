@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+
+use crate::{
+    action::{Action, ActionPlugin},
+    settings::{DisplayMode, Settings, SettingsConfig, SettingsPlugin},
+};
+
+/// Maps devices to actions and handles the ones that aren't part of gameplay
+/// or menus, like toggling fullscreen.
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<ActionPlugin>() {
+            app.add_plugins(ActionPlugin);
+        }
+        if !app.is_plugin_added::<SettingsPlugin>() {
+            app.add_plugins(SettingsPlugin);
+        }
+        app.add_systems(Update, toggle_fullscreen);
+    }
+}
+
+/// Switches between a window and exclusive fullscreen. The window keeps the
+/// resolution from the settings.
+fn toggle_fullscreen(
+    actions: Res<ButtonInput<Action>>,
+    mut settings: ResMut<Settings>,
+    config: Res<SettingsConfig>,
+) {
+    if actions.just_pressed(Action::ToggleFullscreen) {
+        settings.display_mode = match settings.display_mode {
+            DisplayMode::Windowed => DisplayMode::Fullscreen,
+            DisplayMode::Borderless | DisplayMode::Fullscreen => DisplayMode::Windowed,
+        };
+        settings.save(&config);
+    }
+}
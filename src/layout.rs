@@ -0,0 +1,124 @@
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowResized},
+};
+use std::f32::consts::PI;
+
+/// Radius of the ring's outer edge in world units. Everything around the ring
+/// is sized from it, and the camera is scaled so that it fits the window.
+pub const RING_RADIUS: f32 = 300.;
+/// Angular half width of the line, used both to draw it and to test it
+/// against targets.
+pub(crate) const LINE_HALF_WIDTH: f32 = PI / 64.;
+/// How far the grade shown for a hit appears from the centre.
+pub(crate) const LABEL_RADIUS: f32 = RING_RADIUS * 1.13;
+/// The ring's radius as a fraction of the smaller side of the window.
+const RING_SCREEN_FRACTION: f32 = 5. / 12.;
+
+/// Inner and outer radius of one of the parts drawn around the ring, in world
+/// units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Band {
+    pub inner: f32,
+    pub outer: f32,
+}
+
+pub(crate) const RING: Band = Band {
+    inner: RING_RADIUS * 0.9,
+    outer: RING_RADIUS,
+};
+/// The line sticks out of the ring on both sides.
+pub(crate) const LINE: Band = Band {
+    inner: RING_RADIUS * 0.8,
+    outer: RING_RADIUS * 1.1,
+};
+pub(crate) const TARGET: Band = Band {
+    inner: RING_RADIUS * 0.86,
+    outer: RING_RADIUS * 1.04,
+};
+
+/// The camera that shows the ring. Only this camera is scaled to the layout,
+/// so other cameras in a host app are left alone. A host can put this on a
+/// camera of its own during `Startup`, and `GameplayPlugin` then doesn't
+/// spawn one.
+#[derive(Component)]
+pub struct GameCamera;
+
+/// How big the ring is on screen, derived from the size of the window.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    /// Radius of the ring's outer edge in logical pixels.
+    pub ring_radius: f32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::from_window_size(Vec2::new(1280., 720.))
+    }
+}
+
+impl Layout {
+    pub fn from_window_size(size: Vec2) -> Self {
+        Self {
+            ring_radius: size.min_element().max(1.) * RING_SCREEN_FRACTION,
+        }
+    }
+
+    /// World units per logical pixel, for the camera's projection.
+    pub fn projection_scale(&self) -> f32 {
+        RING_RADIUS / self.ring_radius
+    }
+}
+
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Layout>()
+            .add_systems(Startup, init_layout)
+            .add_systems(
+                Update,
+                (
+                    update_layout,
+                    scale_camera.run_if(resource_changed::<Layout>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn init_layout(mut layout: ResMut<Layout>, window: Query<&Window, With<PrimaryWindow>>) {
+    if let Ok(window) = window.get_single() {
+        layout.set_if_neq(Layout::from_window_size(window.size()));
+    }
+}
+
+fn update_layout(
+    mut resized: EventReader<WindowResized>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut layout: ResMut<Layout>,
+) {
+    let Ok(primary) = window.get_single() else {
+        resized.clear();
+        return;
+    };
+    if let Some(event) = resized
+        .read()
+        .filter(|event| event.window == primary)
+        .last()
+    {
+        layout.set_if_neq(Layout::from_window_size(Vec2::new(
+            event.width,
+            event.height,
+        )));
+    }
+}
+
+fn scale_camera(
+    layout: Res<Layout>,
+    mut projection: Query<&mut OrthographicProjection, With<GameCamera>>,
+) {
+    for mut projection in projection.iter_mut() {
+        projection.scale = layout.projection_scale();
+    }
+}
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+mod action;
+mod arc;
+mod audio;
+mod combo;
+mod difficulty;
+mod gameplay;
+mod grade;
+mod headless;
+mod highscores;
+mod input;
+mod layout;
+mod menu;
+mod motion;
+mod replay;
+mod rng;
+mod ron_file;
+mod settings;
+mod state;
+mod ui;
+
+pub use action::{Action, ActionPlugin, Binding, Bindings, BindingsConfig};
+pub use arc::{arcs_overlap, wrap_angle, RingAngle};
+pub use audio::{
+    pitch_for_speed, tempo_for_speed, MusicTrack, SoundPlugin, Tone, ToneDecoder, Waveform,
+};
+pub use combo::{Combo, ComboRules};
+pub use difficulty::{
+    Behaviour, Difficulty, DifficultyConfig, DifficultyCurve, DifficultyKeyframe, DifficultyPlugin,
+    TargetOrder,
+};
+pub use gameplay::{
+    BestScore, Countdown, GameTick, GameplayPlugin, Invulnerable, Lives, Missed, RotationSpeed,
+    RunStats, Score, StartingLives, StrictMisses, TargetHit, TargetZone, TickRate, WindowFocus,
+};
+pub use grade::Grade;
+pub use headless::{HeadlessPlugin, HEADLESS_TICK_RATE};
+pub use highscores::{HighScoreConfig, HighScoreEntry, HighScorePlugin, HighScores};
+pub use input::InputPlugin;
+pub use layout::{GameCamera, Layout, LayoutPlugin, RING_RADIUS};
+pub use menu::{MenuPage, MenuPlugin, PausePage};
+pub use motion::{CounterRotating, Drift, Oscillating, TeleportTimer};
+pub use replay::{Replay, ReplayConfig, ReplayPlayback, ReplayPlugin, REPLAY_VERSION};
+pub use rng::{GameRng, RngSeed};
+pub use settings::{DisplayMode, Settings, SettingsConfig, SettingsPlugin, Theme, RESOLUTIONS};
+pub use state::{GameMode, GameState};
+pub use ui::{ScoreText, UiPlugin};
+
+/// Adds the whole lock minigame to an app that already has `DefaultPlugins`.
+pub struct SpinnyLockPlugin;
+
+impl Plugin for SpinnyLockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            GameplayPlugin,
+            UiPlugin,
+            InputPlugin,
+            HighScorePlugin,
+            ReplayPlugin,
+            SoundPlugin,
+        ));
+    }
+}
@@ -0,0 +1,817 @@
+use bevy::{
+    prelude::*,
+    window::{Monitor, PrimaryMonitor},
+};
+
+use crate::{
+    action::{Action, Binding, Bindings, BindingsConfig},
+    highscores::HighScores,
+    settings::{selected_monitor, Settings, SettingsConfig, SettingsPlugin},
+    state::{GameMode, GameState},
+};
+
+const BUTTON_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const SELECTED_BUTTON_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
+const PAUSE_BACKGROUND_COLOR: Color = Color::srgba(0., 0., 0., 0.6);
+const IDLE_BINDING_PROMPT: &str = "Select an action to add an input to it";
+
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[source(GameState = GameState::MainMenu)]
+pub enum MenuPage {
+    #[default]
+    Title,
+    Settings,
+    Display,
+    Controls,
+    HighScores,
+}
+
+/// Pages of the pause menu, shown over the frozen run.
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[source(GameState = GameState::Paused)]
+pub enum PausePage {
+    #[default]
+    Menu,
+    Settings,
+    Display,
+    Controls,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum MenuAction {
+    Play,
+    CycleMode,
+    CycleOption(SettingOption),
+    Settings,
+    Display,
+    Controls,
+    Rebind(Action),
+    ResetBindings,
+    HighScores,
+    Quit,
+    Back,
+    Resume,
+    Restart,
+    QuitToMenu,
+}
+
+/// Position of a button in keyboard and gamepad navigation order.
+#[derive(Component)]
+struct MenuItem(usize);
+
+#[derive(Resource, Default)]
+struct MenuSelection(usize);
+
+#[derive(Event)]
+struct MenuActivated(MenuAction);
+
+#[derive(Component)]
+struct MenuScreen;
+
+/// What Escape or the gamepad's East button does on a page. Pages without
+/// one, like the title page, ignore them.
+#[derive(Component)]
+struct BackAction(MenuAction);
+
+#[derive(Component)]
+struct ModeLabel;
+
+/// A setting that a button steps through the values of.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingOption {
+    DisplayMode,
+    Monitor,
+    Resolution,
+    FullscreenResolution,
+    Vsync,
+    Volume,
+    MusicVolume,
+    SfxVolume,
+    Theme,
+    StrictMisses,
+}
+
+/// Shows the current value of a setting on its button.
+#[derive(Component)]
+struct OptionLabel(SettingOption);
+
+/// Lists the inputs bound to an action on the controls page.
+#[derive(Component)]
+struct BindingsLabel(Action);
+
+/// Tells the player what the controls page is waiting for.
+#[derive(Component)]
+struct BindingPrompt;
+
+/// Set while the controls page waits for an input to bind to the action.
+/// Menu navigation is ignored meanwhile so the input only binds.
+#[derive(Resource)]
+struct Rebinding(Action);
+
+#[derive(Event)]
+struct BindingCaptured(Binding);
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<SettingsPlugin>() {
+            app.add_plugins(SettingsPlugin);
+        }
+        app.add_sub_state::<MenuPage>()
+            .add_sub_state::<PausePage>()
+            .init_resource::<MenuSelection>()
+            .add_event::<MenuActivated>()
+            .add_event::<BindingCaptured>()
+            .add_systems(OnEnter(MenuPage::Title), spawn_title_page)
+            .add_systems(OnEnter(MenuPage::Settings), spawn_settings_page)
+            .add_systems(OnEnter(MenuPage::Display), spawn_display_page)
+            .add_systems(OnEnter(MenuPage::Controls), spawn_controls_page)
+            .add_systems(OnEnter(MenuPage::HighScores), spawn_high_scores_page)
+            .add_systems(OnExit(MenuPage::Title), despawn_menu_screen)
+            .add_systems(OnExit(MenuPage::Settings), despawn_menu_screen)
+            .add_systems(OnExit(MenuPage::Display), despawn_menu_screen)
+            .add_systems(
+                OnExit(MenuPage::Controls),
+                (despawn_menu_screen, cancel_rebinding),
+            )
+            .add_systems(OnExit(MenuPage::HighScores), despawn_menu_screen)
+            .add_systems(OnEnter(PausePage::Menu), spawn_pause_page)
+            .add_systems(OnEnter(PausePage::Settings), spawn_settings_page)
+            .add_systems(OnExit(PausePage::Menu), despawn_menu_screen)
+            .add_systems(OnEnter(PausePage::Display), spawn_display_page)
+            .add_systems(OnEnter(PausePage::Controls), spawn_controls_page)
+            .add_systems(OnExit(PausePage::Settings), despawn_menu_screen)
+            .add_systems(OnExit(PausePage::Display), despawn_menu_screen)
+            .add_systems(
+                OnExit(PausePage::Controls),
+                (despawn_menu_screen, cancel_rebinding),
+            )
+            .add_systems(
+                Update,
+                (
+                    navigate_menu,
+                    activate_menu_item,
+                    (handle_menu_action, toggle_options, handle_controls_action),
+                    highlight_selection,
+                    (capture_binding, apply_binding)
+                        .chain()
+                        .run_if(resource_exists::<Rebinding>),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu).or(in_state(GameState::Paused))),
+            )
+            .add_systems(
+                Update,
+                update_bindings_labels.run_if(resource_changed::<Bindings>),
+            )
+            .add_systems(
+                Update,
+                update_mode_label
+                    .run_if(in_state(MenuPage::Title).and(resource_changed::<GameMode>)),
+            )
+            .add_systems(
+                Update,
+                update_option_labels.run_if(resource_changed::<Settings>),
+            );
+    }
+}
+
+fn spawn_page_root(commands: &mut Commands, selection: &mut MenuSelection) -> Entity {
+    selection.0 = 0;
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            MenuScreen,
+        ))
+        .id()
+}
+
+fn spawn_title(parent: &mut ChildBuilder, title: &str) {
+    parent.spawn((
+        Text::new(title),
+        TextFont {
+            font_size: 100.0,
+            ..default()
+        },
+        Node {
+            margin: UiRect::bottom(Val::Px(30.0)),
+            ..default()
+        },
+    ));
+}
+
+fn spawn_menu_button(parent: &mut ChildBuilder, index: usize, label: &str, action: MenuAction) {
+    spawn_menu_button_with(parent, index, label, action, ());
+}
+
+/// Like `spawn_menu_button`, with extra components on the button's text.
+fn spawn_menu_button_with(
+    parent: &mut ChildBuilder,
+    index: usize,
+    label: &str,
+    action: MenuAction,
+    text_components: impl Bundle,
+) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(320.0),
+                padding: UiRect::axes(Val::Px(20.0), Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(BUTTON_COLOR),
+            MenuItem(index),
+            action,
+        ))
+        .with_child((
+            Text::new(label),
+            TextFont {
+                font_size: 36.0,
+                ..default()
+            },
+            text_components,
+        ));
+}
+
+fn spawn_line(parent: &mut ChildBuilder, line: String) {
+    parent.spawn((
+        Text::new(line),
+        TextFont {
+            font_size: 28.0,
+            ..default()
+        },
+    ));
+}
+
+fn spawn_title_page(
+    mut commands: Commands,
+    mut selection: ResMut<MenuSelection>,
+    mode: Res<GameMode>,
+    config: Res<SettingsConfig>,
+) {
+    let root = spawn_page_root(&mut commands, &mut selection);
+    commands.entity(root).with_children(|parent| {
+        spawn_title(parent, "SpinnyLock");
+        spawn_menu_button(parent, 0, "Play", MenuAction::Play);
+        spawn_menu_button_with(
+            parent,
+            1,
+            &mode_label(*mode),
+            MenuAction::CycleMode,
+            ModeLabel,
+        );
+        spawn_menu_button(parent, 2, "Settings", MenuAction::Settings);
+        spawn_menu_button(parent, 3, "High Scores", MenuAction::HighScores);
+        // Quitting closes the whole app, which is only the game's to close
+        // when it has the window to itself.
+        if config.manage_window {
+            spawn_menu_button(parent, 4, "Quit", MenuAction::Quit);
+        }
+    });
+}
+
+fn spawn_settings_page(
+    mut commands: Commands,
+    mut selection: ResMut<MenuSelection>,
+    game_state: Res<State<GameState>>,
+    settings: Res<Settings>,
+) {
+    let root = spawn_page_root(&mut commands, &mut selection);
+    let paused = *game_state.get() == GameState::Paused;
+    if paused {
+        commands
+            .entity(root)
+            .insert(BackgroundColor(PAUSE_BACKGROUND_COLOR));
+    }
+    commands
+        .entity(root)
+        .insert(BackAction(MenuAction::Back))
+        .with_children(|parent| {
+            spawn_title(parent, "Settings");
+            spawn_menu_button(parent, 0, "Controls", MenuAction::Controls);
+            spawn_menu_button(parent, 1, "Display", MenuAction::Display);
+            spawn_option_button(parent, 2, SettingOption::Volume, &settings);
+            spawn_option_button(parent, 3, SettingOption::MusicVolume, &settings);
+            spawn_option_button(parent, 4, SettingOption::SfxVolume, &settings);
+            // Rules can't change in the middle of a run.
+            let mut index = 5;
+            if !paused {
+                spawn_option_button(parent, index, SettingOption::StrictMisses, &settings);
+                index += 1;
+            }
+            spawn_menu_button(parent, index, "Back", MenuAction::Back);
+        });
+}
+
+fn spawn_display_page(
+    mut commands: Commands,
+    mut selection: ResMut<MenuSelection>,
+    game_state: Res<State<GameState>>,
+    settings: Res<Settings>,
+) {
+    let root = spawn_page_root(&mut commands, &mut selection);
+    if *game_state.get() == GameState::Paused {
+        commands
+            .entity(root)
+            .insert(BackgroundColor(PAUSE_BACKGROUND_COLOR));
+    }
+    commands
+        .entity(root)
+        .insert(BackAction(MenuAction::Settings))
+        .with_children(|parent| {
+            spawn_title(parent, "Display");
+            let options = [
+                SettingOption::DisplayMode,
+                SettingOption::Monitor,
+                SettingOption::Resolution,
+                SettingOption::FullscreenResolution,
+                SettingOption::Vsync,
+                SettingOption::Theme,
+            ];
+            for (index, option) in options.into_iter().enumerate() {
+                spawn_option_button(parent, index, option, &settings);
+            }
+            spawn_menu_button(parent, options.len(), "Back", MenuAction::Settings);
+        });
+}
+
+fn spawn_option_button(
+    parent: &mut ChildBuilder,
+    index: usize,
+    option: SettingOption,
+    settings: &Settings,
+) {
+    spawn_menu_button_with(
+        parent,
+        index,
+        &option_label(option, settings),
+        MenuAction::CycleOption(option),
+        OptionLabel(option),
+    );
+}
+
+fn spawn_controls_page(
+    mut commands: Commands,
+    mut selection: ResMut<MenuSelection>,
+    game_state: Res<State<GameState>>,
+    bindings: Res<Bindings>,
+) {
+    let root = spawn_page_root(&mut commands, &mut selection);
+    if *game_state.get() == GameState::Paused {
+        commands
+            .entity(root)
+            .insert(BackgroundColor(PAUSE_BACKGROUND_COLOR));
+    }
+    commands
+        .entity(root)
+        .insert(BackAction(MenuAction::Settings))
+        .with_children(|parent| {
+            // No big title, to leave room for a row per action.
+            spawn_line(parent, "Controls".to_string());
+            for (index, action) in Action::ALL.into_iter().enumerate() {
+                parent
+                    .spawn(Node {
+                        width: Val::Px(960.0),
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        spawn_menu_button(row, index, action.name(), MenuAction::Rebind(action));
+                        row.spawn((
+                            Text::new(bindings_label(&bindings, action)),
+                            TextFont {
+                                font_size: 28.0,
+                                ..default()
+                            },
+                            BindingsLabel(action),
+                        ));
+                    });
+            }
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(20.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    let index = Action::ALL.len();
+                    spawn_menu_button(row, index, "Reset", MenuAction::ResetBindings);
+                    spawn_menu_button(row, index + 1, "Back", MenuAction::Settings);
+                });
+            parent.spawn((
+                Text::new(IDLE_BINDING_PROMPT),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                BindingPrompt,
+            ));
+        });
+}
+
+fn spawn_pause_page(mut commands: Commands, mut selection: ResMut<MenuSelection>) {
+    let root = spawn_page_root(&mut commands, &mut selection);
+    commands
+        .entity(root)
+        .insert((
+            BackgroundColor(PAUSE_BACKGROUND_COLOR),
+            BackAction(MenuAction::Resume),
+        ))
+        .with_children(|parent| {
+            spawn_title(parent, "Paused");
+            spawn_menu_button(parent, 0, "Resume", MenuAction::Resume);
+            spawn_menu_button(parent, 1, "Restart", MenuAction::Restart);
+            spawn_menu_button(parent, 2, "Settings", MenuAction::Settings);
+            spawn_menu_button(parent, 3, "Quit to Menu", MenuAction::QuitToMenu);
+        });
+}
+
+fn spawn_high_scores_page(
+    mut commands: Commands,
+    mut selection: ResMut<MenuSelection>,
+    high_scores: Option<Res<HighScores>>,
+) {
+    let root = spawn_page_root(&mut commands, &mut selection);
+    commands
+        .entity(root)
+        .insert(BackAction(MenuAction::Back))
+        .with_children(|parent| {
+            spawn_title(parent, "High Scores");
+            let entries = high_scores
+                .as_ref()
+                .map(|high_scores| high_scores.entries.as_slice())
+                .unwrap_or_default();
+            if entries.is_empty() {
+                spawn_line(parent, "No high scores yet".to_string());
+            }
+            for (rank, entry) in entries.iter().enumerate() {
+                spawn_line(
+                    parent,
+                    format!(
+                        "{}. {}  {}  {}  {}",
+                        rank + 1,
+                        entry.name,
+                        entry.score,
+                        entry.mode.name(),
+                        entry.date
+                    ),
+                );
+            }
+            spawn_menu_button(parent, 0, "Back", MenuAction::Back);
+        });
+}
+
+fn despawn_menu_screen(mut commands: Commands, query: Query<Entity, With<MenuScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn navigate_menu(
+    actions: Res<ButtonInput<Action>>,
+    items: Query<(&MenuItem, &Interaction), Changed<Interaction>>,
+    all_items: Query<&MenuItem>,
+    mut selection: ResMut<MenuSelection>,
+    rebinding: Option<Res<Rebinding>>,
+) {
+    let item_count = all_items.iter().count();
+    if item_count == 0 || rebinding.is_some() {
+        return;
+    }
+
+    if actions.just_pressed(Action::Up) {
+        selection.0 = (selection.0 + item_count - 1) % item_count;
+    }
+    if actions.just_pressed(Action::Down) {
+        selection.0 = (selection.0 + 1) % item_count;
+    }
+
+    for (item, interaction) in items.iter() {
+        if *interaction == Interaction::Hovered {
+            selection.0 = item.0;
+        }
+    }
+}
+
+fn activate_menu_item(
+    actions: Res<ButtonInput<Action>>,
+    items: Query<(&MenuItem, &MenuAction, &Interaction)>,
+    clicked: Query<(&MenuAction, &Interaction), Changed<Interaction>>,
+    selection: Res<MenuSelection>,
+    back_action: Query<&BackAction>,
+    mut activated: EventWriter<MenuActivated>,
+    rebinding: Option<Res<Rebinding>>,
+) {
+    // Still runs while rebinding, so that the clicks it binds are seen as old
+    // once it ends.
+    if rebinding.is_some() {
+        return;
+    }
+    for (action, interaction) in clicked.iter() {
+        if *interaction == Interaction::Pressed {
+            activated.send(MenuActivated(*action));
+            return;
+        }
+    }
+
+    if actions.just_pressed(Action::Confirm) {
+        if let Some((_, action, _)) = items.iter().find(|(item, _, _)| item.0 == selection.0) {
+            activated.send(MenuActivated(*action));
+        }
+        return;
+    }
+
+    let back = actions.just_pressed(Action::Back);
+    let pause = actions.just_pressed(Action::Pause);
+    for BackAction(action) in back_action.iter() {
+        if back || (pause && *action == MenuAction::Resume) {
+            activated.send(MenuActivated(*action));
+        }
+    }
+}
+
+fn handle_menu_action(
+    mut activated: EventReader<MenuActivated>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_page: ResMut<NextState<MenuPage>>,
+    mut next_pause_page: ResMut<NextState<PausePage>>,
+    game_state: Res<State<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let paused = *game_state.get() == GameState::Paused;
+    for MenuActivated(action) in activated.read() {
+        match action {
+            MenuAction::Play | MenuAction::Restart => next_state.set(GameState::Countdown),
+            MenuAction::CycleMode
+            | MenuAction::CycleOption(_)
+            | MenuAction::Rebind(_)
+            | MenuAction::ResetBindings => {}
+            MenuAction::Settings if paused => next_pause_page.set(PausePage::Settings),
+            MenuAction::Settings => next_page.set(MenuPage::Settings),
+            MenuAction::Display if paused => next_pause_page.set(PausePage::Display),
+            MenuAction::Display => next_page.set(MenuPage::Display),
+            MenuAction::Controls if paused => next_pause_page.set(PausePage::Controls),
+            MenuAction::Controls => next_page.set(MenuPage::Controls),
+            MenuAction::HighScores => next_page.set(MenuPage::HighScores),
+            MenuAction::Quit => {
+                exit.send(AppExit::Success);
+            }
+            MenuAction::Back if paused => next_pause_page.set(PausePage::Menu),
+            MenuAction::Back => next_page.set(MenuPage::Title),
+            MenuAction::Resume => next_state.set(GameState::Playing),
+            MenuAction::QuitToMenu => next_state.set(GameState::MainMenu),
+        }
+    }
+}
+
+/// Handles the buttons that change a setting in place, saving each change.
+fn toggle_options(
+    mut activated: EventReader<MenuActivated>,
+    mut settings: ResMut<Settings>,
+    config: Res<SettingsConfig>,
+    monitors: Query<(Entity, &Monitor, Has<PrimaryMonitor>)>,
+) {
+    for MenuActivated(action) in activated.read() {
+        match action {
+            MenuAction::CycleMode => settings.mode = settings.mode.next(),
+            MenuAction::CycleOption(option) => match option {
+                SettingOption::DisplayMode => {
+                    settings.display_mode = settings.display_mode.next();
+                }
+                SettingOption::Monitor => {
+                    settings.monitor = settings.next_monitor(monitors.iter().len());
+                    // Video modes differ between monitors, and a window moved
+                    // to another monitor is centred on it.
+                    settings.fullscreen_resolution = None;
+                    settings.window_position = None;
+                }
+                SettingOption::Resolution => settings.resolution = settings.next_resolution(),
+                SettingOption::FullscreenResolution => {
+                    let Some(monitor) = selected_monitor(&settings, monitors.iter()) else {
+                        continue;
+                    };
+                    settings.fullscreen_resolution = settings.next_fullscreen_resolution(monitor);
+                }
+                SettingOption::Vsync => settings.vsync = !settings.vsync,
+                SettingOption::Volume => settings.volume = Settings::next_volume(settings.volume),
+                SettingOption::MusicVolume => {
+                    settings.music_volume = Settings::next_volume(settings.music_volume);
+                }
+                SettingOption::SfxVolume => {
+                    settings.sfx_volume = Settings::next_volume(settings.sfx_volume);
+                }
+                SettingOption::Theme => settings.theme = settings.theme.next(),
+                SettingOption::StrictMisses => settings.strict_misses = !settings.strict_misses,
+            },
+            _ => continue,
+        }
+        settings.save(&config);
+    }
+}
+
+fn handle_controls_action(
+    mut commands: Commands,
+    mut activated: EventReader<MenuActivated>,
+    mut bindings: ResMut<Bindings>,
+    config: Res<BindingsConfig>,
+    mut prompt: Query<&mut Text, With<BindingPrompt>>,
+) {
+    for MenuActivated(action) in activated.read() {
+        match *action {
+            MenuAction::Rebind(action) => {
+                commands.insert_resource(Rebinding(action));
+                set_prompt(
+                    &mut prompt,
+                    format!(
+                        "Press an input for {} - Delete clears it, Backspace cancels",
+                        action.name()
+                    ),
+                );
+            }
+            MenuAction::ResetBindings => {
+                *bindings = Bindings::default();
+                bindings.save(&config);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Picks up the next input pressed while rebinding.
+fn capture_binding(
+    rebinding: Res<Rebinding>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    gamepads: Query<&Gamepad>,
+    mut captured: EventWriter<BindingCaptured>,
+) {
+    // The press that started rebinding isn't the one to bind.
+    if rebinding.is_added() {
+        return;
+    }
+    let binding = keyboard
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+        })
+        .or_else(|| touches.any_just_pressed().then_some(Binding::Touch))
+        .or_else(|| {
+            gamepads.iter().find_map(|gamepad| {
+                gamepad
+                    .get_just_pressed()
+                    .next()
+                    .map(|button| Binding::Gamepad(*button))
+            })
+        });
+    if let Some(binding) = binding {
+        captured.send(BindingCaptured(binding));
+    }
+}
+
+/// Binds the captured input unless another action that is used in the same
+/// place already has it, in which case the page keeps waiting.
+fn apply_binding(
+    mut commands: Commands,
+    mut captured: EventReader<BindingCaptured>,
+    rebinding: Res<Rebinding>,
+    mut bindings: ResMut<Bindings>,
+    config: Res<BindingsConfig>,
+    mut actions: ResMut<ButtonInput<Action>>,
+    mut prompt: Query<&mut Text, With<BindingPrompt>>,
+) {
+    let Some(BindingCaptured(binding)) = captured.read().last() else {
+        return;
+    };
+    let action = rebinding.0;
+    match *binding {
+        Binding::Key(KeyCode::Backspace) => {}
+        Binding::Key(KeyCode::Delete) => {
+            bindings.clear(action);
+            bindings.save(&config);
+        }
+        binding => {
+            if let Some(other) = bindings.conflict(action, binding) {
+                set_prompt(
+                    &mut prompt,
+                    format!(
+                        "{} is already used by {} - try another",
+                        binding.name(),
+                        other.name()
+                    ),
+                );
+                return;
+            }
+            bindings.bind(action, binding);
+            bindings.save(&config);
+        }
+    }
+    commands.remove_resource::<Rebinding>();
+    // Keep the press from also doing whatever it is now bound to.
+    actions.reset_all();
+    set_prompt(&mut prompt, IDLE_BINDING_PROMPT.to_string());
+}
+
+fn cancel_rebinding(mut commands: Commands) {
+    commands.remove_resource::<Rebinding>();
+}
+
+fn set_prompt(prompt: &mut Query<&mut Text, With<BindingPrompt>>, message: String) {
+    for mut text in prompt.iter_mut() {
+        **text = message.clone();
+    }
+}
+
+fn update_bindings_labels(bindings: Res<Bindings>, mut query: Query<(&mut Text, &BindingsLabel)>) {
+    for (mut text, BindingsLabel(action)) in query.iter_mut() {
+        **text = bindings_label(&bindings, *action);
+    }
+}
+
+fn bindings_label(bindings: &Bindings, action: Action) -> String {
+    let names: Vec<String> = bindings
+        .get(action)
+        .iter()
+        .map(|binding| binding.name())
+        .collect();
+    if names.is_empty() {
+        "Unbound".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+fn update_mode_label(mode: Res<GameMode>, mut query: Query<&mut Text, With<ModeLabel>>) {
+    for mut text in query.iter_mut() {
+        **text = mode_label(*mode);
+    }
+}
+
+fn mode_label(mode: GameMode) -> String {
+    format!("Mode: {}", mode.name())
+}
+
+fn update_option_labels(settings: Res<Settings>, mut query: Query<(&mut Text, &OptionLabel)>) {
+    for (mut text, OptionLabel(option)) in query.iter_mut() {
+        **text = option_label(*option, &settings);
+    }
+}
+
+fn option_label(option: SettingOption, settings: &Settings) -> String {
+    let on_off = |on: bool| if on { "On" } else { "Off" };
+    match option {
+        SettingOption::DisplayMode => format!("Window: {}", settings.display_mode.name()),
+        SettingOption::Monitor => match settings.monitor {
+            Some(index) => format!("Monitor: {}", index + 1),
+            None => "Monitor: Current".to_string(),
+        },
+        SettingOption::Resolution => {
+            let (width, height) = settings.resolution;
+            format!("Window size: {width}x{height}")
+        }
+        SettingOption::FullscreenResolution => match settings.fullscreen_resolution {
+            Some((width, height)) => format!("Fullscreen: {width}x{height}"),
+            None => "Fullscreen: Largest".to_string(),
+        },
+        SettingOption::Vsync => format!("VSync: {}", on_off(settings.vsync)),
+        SettingOption::Volume => format!("Volume: {:.0}%", settings.volume * 100.),
+        SettingOption::MusicVolume => format!("Music: {:.0}%", settings.music_volume * 100.),
+        SettingOption::SfxVolume => format!("Effects: {:.0}%", settings.sfx_volume * 100.),
+        SettingOption::Theme => format!("Theme: {}", settings.theme.name()),
+        SettingOption::StrictMisses => {
+            format!("Strict misses: {}", on_off(settings.strict_misses))
+        }
+    }
+}
+
+fn highlight_selection(
+    selection: Res<MenuSelection>,
+    mut items: Query<(&MenuItem, &mut BackgroundColor)>,
+) {
+    for (item, mut background) in items.iter_mut() {
+        let color = if item.0 == selection.0 {
+            SELECTED_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+}
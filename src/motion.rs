@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+use crate::{
+    arc::RingAngle,
+    gameplay::{pick_free_angle, RotationSpeed, TargetZone},
+    layout::LINE_HALF_WIDTH,
+    rng::GameRng,
+};
+
+/// Moves a target around the ring at a constant speed, in radians per
+/// second.
+#[derive(Component)]
+pub struct Drift(pub f32);
+
+/// Swings a target back and forth around where it was placed.
+#[derive(Component)]
+pub struct Oscillating {
+    /// Furthest the target gets from its centre, in radians.
+    pub amplitude: f32,
+    /// Seconds for one full swing there and back.
+    pub period: f32,
+    pub elapsed: f32,
+}
+
+impl Oscillating {
+    pub fn new(amplitude: f32, period: f32) -> Self {
+        Self {
+            amplitude,
+            period,
+            elapsed: 0.,
+        }
+    }
+
+    fn offset(&self) -> f32 {
+        self.amplitude * ops::sin(TAU * self.elapsed / self.period)
+    }
+}
+
+/// Moves a target against the line's direction at this fraction of the
+/// line's speed.
+#[derive(Component)]
+pub struct CounterRotating(pub f32);
+
+/// Moves a target to a new random angle every time the timer finishes.
+#[derive(Component)]
+pub struct TeleportTimer(pub Timer);
+
+// The motions only ever add to `current`, so they can be mixed on one target.
+
+pub(crate) fn drift_targets(time: Res<Time>, mut query: Query<(&Drift, &mut RingAngle)>) {
+    for (Drift(speed), mut angle) in query.iter_mut() {
+        angle.current += speed * time.delta_secs();
+    }
+}
+
+pub(crate) fn oscillate_targets(
+    time: Res<Time>,
+    mut query: Query<(&mut Oscillating, &mut RingAngle)>,
+) {
+    for (mut oscillating, mut angle) in query.iter_mut() {
+        let before = oscillating.offset();
+        oscillating.elapsed += time.delta_secs();
+        angle.current += oscillating.offset() - before;
+    }
+}
+
+pub(crate) fn counter_rotate_targets(
+    time: Res<Time>,
+    line: Query<&RotationSpeed>,
+    mut query: Query<(&CounterRotating, &mut RingAngle)>,
+) {
+    let Ok(line_speed) = line.get_single() else {
+        return;
+    };
+    // The line turns by -speed, so turning by +speed goes against it.
+    for (CounterRotating(factor), mut angle) in query.iter_mut() {
+        angle.current += factor * line_speed.0 * time.delta_secs();
+    }
+}
+
+pub(crate) fn teleport_targets(
+    time: Res<Time>,
+    mut game_rng: ResMut<GameRng>,
+    mut timers: Query<(Entity, &mut TeleportTimer)>,
+    mut targets: Query<(Entity, &TargetZone, &mut RingAngle)>,
+) {
+    for (entity, mut timer) in timers.iter_mut() {
+        if !timer.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let occupied: Vec<(f32, f32)> = targets
+            .iter()
+            .filter(|(other, _, _)| *other != entity)
+            .map(|(_, target, angle)| (angle.current, target.half_width + LINE_HALF_WIDTH))
+            .collect();
+        let Ok((_, target, mut angle)) = targets.get_mut(entity) else {
+            continue;
+        };
+        let half_width = target.half_width + LINE_HALF_WIDTH;
+        if let Some(free_angle) = pick_free_angle(game_rng.rng(), half_width, &occupied) {
+            angle.teleport(free_angle);
+        }
+    }
+}
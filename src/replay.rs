@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    combo::ComboRules,
+    difficulty::{DifficultyConfig, DifficultyCurve},
+    gameplay::{
+        reverse_rotate_direction, GameTick, PendingReverse, Score, StartingLives, StrictMisses,
+        TickRate,
+    },
+    rng::{GameRng, RngSeed},
+    ron_file::{read_ron, write_ron},
+    state::{GameMode, GameState},
+};
+
+pub const REPLAY_VERSION: u32 = 4;
+
+/// Everything needed to play a run back tick for tick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub tick_rate: f64,
+    #[serde(default)]
+    pub mode: GameMode,
+    /// The curve the run was played with. Older replays were all played with
+    /// the default one.
+    #[serde(default)]
+    pub difficulty: DifficultyCurve,
+    /// Lives at the start of a `GameMode::Lives` run.
+    #[serde(default)]
+    pub starting_lives: Option<u32>,
+    #[serde(default)]
+    pub strict_misses: bool,
+    #[serde(default)]
+    pub combo_rules: ComboRules,
+    /// The ticks on which the line was reversed, in order.
+    pub inputs: Vec<u64>,
+    pub score: u32,
+}
+
+impl Replay {
+    /// Reads a replay and checks that it can be played back, since replay
+    /// files are shared and may have been edited.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let replay: Replay =
+            read_ron(path)?.ok_or_else(|| format!("{} does not exist", path.display()))?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "{} is a version {} replay, expected version {REPLAY_VERSION}",
+                path.display(),
+                replay.version
+            ));
+        }
+        replay
+            .validated()
+            .map_err(|err| format!("{} can't be played back: {err}", path.display()))
+    }
+
+    /// Rejects replays whose settings would crash the game.
+    pub fn validated(mut self) -> Result<Self, String> {
+        if !self.tick_rate.is_finite() || self.tick_rate <= 0. {
+            return Err(format!(
+                "Tick rate {} is not a positive number",
+                self.tick_rate
+            ));
+        }
+        self.difficulty = self.difficulty.validated()?;
+        Ok(self)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        write_ron(path, self)
+    }
+}
+
+/// Where finished runs are saved.
+#[derive(Resource, Clone)]
+pub struct ReplayConfig {
+    pub directory: PathBuf,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        let directory = dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("SpinnyLock")
+            .join("replays");
+        Self { directory }
+    }
+}
+
+/// Insert this before adding the plugin to play a replay back instead of
+/// taking input from the player.
+#[derive(Resource)]
+pub struct ReplayPlayback(pub Replay);
+
+#[derive(Resource, Default)]
+struct ReplayRecorder {
+    inputs: Vec<u64>,
+    difficulty: DifficultyCurve,
+    starting_lives: Option<u32>,
+    strict_misses: bool,
+    combo_rules: ComboRules,
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let playback = app
+            .world_mut()
+            .remove_resource::<ReplayPlayback>()
+            .map(|ReplayPlayback(replay)| replay.validated());
+        match playback {
+            Some(Err(err)) => error!("Not playing the replay back: {err}"),
+            Some(Ok(replay)) => {
+                app.insert_resource(ReplayPlayback(replay.clone()))
+                    .insert_resource(RngSeed(Some(replay.seed)))
+                    .insert_resource(replay.mode)
+                    .insert_resource(TickRate(replay.tick_rate))
+                    .insert_resource(replay.difficulty)
+                    .insert_resource(StrictMisses(replay.strict_misses))
+                    .insert_resource(replay.combo_rules)
+                    .insert_resource(DifficultyConfig { path: None });
+                if let Some(lives) = replay.starting_lives {
+                    app.insert_resource(StartingLives(lives));
+                }
+            }
+            None => {}
+        }
+
+        app.init_resource::<ReplayConfig>()
+            .init_resource::<ReplayRecorder>()
+            .add_systems(
+                Startup,
+                start_playback.run_if(resource_exists::<ReplayPlayback>),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    feed_replay_inputs.run_if(resource_exists::<ReplayPlayback>),
+                    record_input,
+                )
+                    .chain()
+                    .before(reverse_rotate_direction)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                save_replay.run_if(not(resource_exists::<ReplayPlayback>)),
+            )
+            .add_systems(OnEnter(GameState::Countdown), start_recording);
+    }
+}
+
+fn start_playback(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Countdown);
+}
+
+fn feed_replay_inputs(
+    playback: Res<ReplayPlayback>,
+    tick: Res<GameTick>,
+    mut pending_reverse: ResMut<PendingReverse>,
+) {
+    // Overwrite rather than OR so that live presses are ignored.
+    pending_reverse.0 = playback.0.inputs.binary_search(&tick.0).is_ok();
+}
+
+fn record_input(
+    tick: Res<GameTick>,
+    pending_reverse: Res<PendingReverse>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if pending_reverse.0 {
+        recorder.inputs.push(tick.0);
+    }
+}
+
+fn save_replay(
+    recorder: Res<ReplayRecorder>,
+    config: Res<ReplayConfig>,
+    game_rng: Res<GameRng>,
+    mode: Res<GameMode>,
+    score: Res<Score>,
+    tick_rate: Res<TickRate>,
+) {
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        seed: game_rng.seed(),
+        tick_rate: tick_rate.0,
+        mode: *mode,
+        difficulty: recorder.difficulty.clone(),
+        starting_lives: recorder.starting_lives,
+        strict_misses: recorder.strict_misses,
+        combo_rules: recorder.combo_rules.clone(),
+        inputs: recorder.inputs.clone(),
+        score: score.0,
+    };
+    let file_name = format!(
+        "replay-{}.ron",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    if let Err(err) = replay.save(&config.directory.join(file_name)) {
+        warn!("{err}");
+    }
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    difficulty: Res<DifficultyCurve>,
+    mode: Res<GameMode>,
+    starting_lives: Res<StartingLives>,
+    strict_misses: Res<StrictMisses>,
+    combo_rules: Res<ComboRules>,
+) {
+    recorder.inputs.clear();
+    recorder.difficulty = difficulty.clone();
+    recorder.starting_lives = (*mode == GameMode::Lives).then_some(starting_lives.0);
+    recorder.strict_misses = strict_misses.0;
+    recorder.combo_rules = combo_rules.clone();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Behaviour;
+    use std::fs;
+
+    #[test]
+    fn replays_that_cannot_be_played_back_are_rejected() {
+        let path =
+            std::env::temp_dir().join(format!("spinny-lock-bad-replay-{}.ron", std::process::id()));
+        let replay = Replay {
+            version: REPLAY_VERSION,
+            seed: 1,
+            tick_rate: 0.,
+            mode: GameMode::Classic,
+            difficulty: DifficultyCurve::default(),
+            starting_lives: None,
+            strict_misses: false,
+            combo_rules: ComboRules::default(),
+            inputs: Vec::new(),
+            score: 0,
+        };
+        replay.save(&path).unwrap();
+        let bad_tick_rate = Replay::load(&path);
+
+        let mut difficulty = DifficultyCurve::default();
+        difficulty.keyframes[0].behaviours = vec![Behaviour::Teleport(-1.)];
+        Replay {
+            tick_rate: 60.,
+            difficulty,
+            ..replay
+        }
+        .save(&path)
+        .unwrap();
+        let bad_difficulty = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(bad_tick_rate.is_err());
+        assert!(bad_difficulty.is_err());
+    }
+}
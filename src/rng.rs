@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Fixes the seed used for every run. Without it each run is seeded from the
+/// clock.
+#[derive(Resource, Clone, Copy, Default)]
+pub struct RngSeed(pub Option<u64>);
+
+/// The random source for every gameplay decision. Two runs with the same seed
+/// and the same inputs play out identically.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn from_config(config: RngSeed) -> Self {
+        Self::new(config.0.unwrap_or_else(clock_seed))
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,27 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, io::ErrorKind, path::Path};
+
+/// Reads and parses a RON file. A file that doesn't exist yet is `Ok(None)`,
+/// so that callers can fall back to their defaults without a warning.
+pub(crate) fn read_ron<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("Could not read {}: {err}", path.display())),
+    };
+    ron::from_str(&contents)
+        .map(Some)
+        .map_err(|err| format!("Could not parse {}: {err}", path.display()))
+}
+
+/// Writes `value` to a file as pretty-printed RON, creating its directory
+/// first if needed.
+pub(crate) fn write_ron<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("Could not create {}: {err}", parent.display()))?;
+    }
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| format!("Could not serialize {}: {err}", path.display()))?;
+    fs::write(path, contents).map_err(|err| format!("Could not write {}: {err}", path.display()))
+}
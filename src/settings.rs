@@ -0,0 +1,593 @@
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    window::{Monitor, PresentMode, PrimaryWindow, WindowMode, WindowMoved, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{
+    gameplay::StrictMisses,
+    replay::ReplayPlayback,
+    ron_file::{read_ron, write_ron},
+    state::GameMode,
+};
+
+/// Window sizes the settings menu cycles through.
+pub const RESOLUTIONS: [(u32, u32); 5] = [
+    (1280, 720),
+    (1366, 768),
+    (1600, 900),
+    (1920, 1080),
+    (2560, 1440),
+];
+const MIN_RESOLUTION: (u32, u32) = (320, 240);
+const MAX_RESOLUTION: (u32, u32) = (7680, 4320);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    /// A borderless window covering the monitor.
+    Borderless,
+    /// Exclusive fullscreen.
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Borderless => "Borderless",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            DisplayMode::Borderless => DisplayMode::Fullscreen,
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+        }
+    }
+}
+
+/// Colours of the background, the ring and the line.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl Theme {
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::HighContrast => "High contrast",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::HighContrast,
+            Theme::HighContrast => Theme::Dark,
+        }
+    }
+
+    pub fn background(self) -> Color {
+        match self {
+            Theme::Dark => Color::srgb_u8(43, 44, 47),
+            Theme::Light => Color::srgb(0.9, 0.9, 0.88),
+            Theme::HighContrast => Color::BLACK,
+        }
+    }
+
+    pub fn ring(self) -> Color {
+        match self {
+            Theme::Dark => Color::BLACK,
+            Theme::Light => Color::srgb(0.3, 0.3, 0.35),
+            Theme::HighContrast => Color::WHITE,
+        }
+    }
+
+    pub fn line(self) -> Color {
+        match self {
+            Theme::Dark => Color::linear_rgba(0., 1., 0., 1.),
+            Theme::Light => Color::srgb(0., 0.55, 0.2),
+            Theme::HighContrast => Color::srgb(1., 0.9, 0.),
+        }
+    }
+}
+
+/// Where the settings are saved. With `None` the defaults are used and
+/// nothing is written.
+#[derive(Resource, Clone)]
+pub struct SettingsConfig {
+    pub path: Option<PathBuf>,
+    /// Whether the display settings control the primary window and the clear
+    /// colour, and the title screen offers to quit. Off by default, so that a
+    /// game embedding SpinnyLock keeps its own window; the standalone game
+    /// turns it on.
+    pub manage_window: bool,
+}
+
+impl Default for SettingsConfig {
+    fn default() -> Self {
+        let path = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("SpinnyLock")
+            .join("settings.ron");
+        Self {
+            path: Some(path),
+            manage_window: false,
+        }
+    }
+}
+
+/// Everything the player can set that is remembered between launches.
+/// Fields missing from the file keep their defaults.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub display_mode: DisplayMode,
+    /// Size of the window when it is not fullscreen.
+    pub resolution: (u32, u32),
+    /// Where the window was last left, in physical pixels. `None` leaves it
+    /// to the window manager.
+    pub window_position: Option<(i32, i32)>,
+    /// Index of the monitor to go fullscreen on. `None` uses the one the
+    /// window is on.
+    pub monitor: Option<usize>,
+    /// Video mode for exclusive fullscreen, in physical pixels. `None` uses
+    /// the monitor's largest.
+    pub fullscreen_resolution: Option<(u32, u32)>,
+    pub vsync: bool,
+    /// Master volume, from 0 to 1.
+    pub volume: f32,
+    /// Volume of the background track, from 0 to 1, relative to `volume`.
+    pub music_volume: f32,
+    /// Volume of the sound effects, from 0 to 1, relative to `volume`.
+    pub sfx_volume: f32,
+    pub theme: Theme,
+    pub mode: GameMode,
+    pub strict_misses: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            display_mode: DisplayMode::Windowed,
+            resolution: (1280, 720),
+            window_position: None,
+            monitor: None,
+            fullscreen_resolution: None,
+            vsync: true,
+            volume: 0.8,
+            music_volume: 0.6,
+            sfx_volume: 1.,
+            theme: Theme::Dark,
+            mode: GameMode::Classic,
+            strict_misses: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Pulls values that can't be used back into range.
+    pub fn validated(mut self) -> Self {
+        let (width, height) = self.resolution;
+        self.resolution = (
+            width.clamp(MIN_RESOLUTION.0, MAX_RESOLUTION.0),
+            height.clamp(MIN_RESOLUTION.1, MAX_RESOLUTION.1),
+        );
+        let valid_volume = |volume: f32, default: f32| {
+            if volume.is_nan() {
+                default
+            } else {
+                volume.clamp(0., 1.)
+            }
+        };
+        let defaults = Self::default();
+        self.volume = valid_volume(self.volume, defaults.volume);
+        self.music_volume = valid_volume(self.music_volume, defaults.music_volume);
+        self.sfx_volume = valid_volume(self.sfx_volume, defaults.sfx_volume);
+        self
+    }
+
+    /// The next size in `RESOLUTIONS`, or the first one if the current size
+    /// isn't in the list.
+    pub fn next_resolution(&self) -> (u32, u32) {
+        RESOLUTIONS
+            .iter()
+            .position(|&resolution| resolution == self.resolution)
+            .map_or(RESOLUTIONS[0], |index| {
+                RESOLUTIONS[(index + 1) % RESOLUTIONS.len()]
+            })
+    }
+
+    /// The monitor after the current one, going back to `None` after the
+    /// last of `count` monitors.
+    pub fn next_monitor(&self, count: usize) -> Option<usize> {
+        match self.monitor {
+            None if count > 0 => Some(0),
+            Some(index) if index + 1 < count => Some(index + 1),
+            _ => None,
+        }
+    }
+
+    /// The next of the monitor's video modes, from largest to smallest, and
+    /// then back to the largest with `None`.
+    pub fn next_fullscreen_resolution(&self, monitor: &Monitor) -> Option<(u32, u32)> {
+        let sizes = video_mode_sizes(monitor);
+        match self.fullscreen_resolution {
+            None => sizes.first().copied(),
+            Some(size) => sizes
+                .iter()
+                .position(|&other| other == size)
+                .and_then(|index| sizes.get(index + 1).copied()),
+        }
+    }
+
+    pub fn monitor_selection(&self) -> MonitorSelection {
+        self.monitor
+            .map_or(MonitorSelection::Current, MonitorSelection::Index)
+    }
+
+    /// The primary window as the settings describe it, for
+    /// `WindowPlugin::primary_window`. Starting with it avoids the window
+    /// opening at the default size and then changing once the settings are
+    /// applied.
+    pub fn window(&self) -> Window {
+        let mut window = Window {
+            mode: self.window_mode(),
+            position: self.window_position(),
+            present_mode: self.present_mode(),
+            ..default()
+        };
+        let (width, height) = self.resolution;
+        window.resolution.set(width as f32, height as f32);
+        if let (DisplayMode::Fullscreen, Some((width, height))) =
+            (self.display_mode, self.fullscreen_resolution)
+        {
+            window.resolution.set_physical_resolution(width, height);
+        }
+        window
+    }
+
+    fn window_mode(&self) -> WindowMode {
+        let monitor = self.monitor_selection();
+        match (self.display_mode, self.fullscreen_resolution) {
+            (DisplayMode::Windowed, _) => WindowMode::Windowed,
+            (DisplayMode::Borderless, _) => WindowMode::BorderlessFullscreen(monitor),
+            // The window's size picks the closest video mode.
+            (DisplayMode::Fullscreen, Some(_)) => WindowMode::SizedFullscreen(monitor),
+            (DisplayMode::Fullscreen, None) => WindowMode::Fullscreen(monitor),
+        }
+    }
+
+    fn window_position(&self) -> WindowPosition {
+        match self.window_position {
+            Some((x, y)) => WindowPosition::At(IVec2::new(x, y)),
+            None => WindowPosition::Centered(self.monitor_selection()),
+        }
+    }
+
+    fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    /// Steps a volume up by 10%, wrapping round to silent after full.
+    pub fn next_volume(volume: f32) -> f32 {
+        let step = (volume * 10.).round() as u32;
+        ((step + 1) % 11) as f32 / 10.
+    }
+
+    pub fn load(config: &SettingsConfig) -> Self {
+        let Some(path) = &config.path else {
+            return Self::default();
+        };
+        match read_ron::<Settings>(path) {
+            Ok(Some(settings)) => {
+                let validated = settings.clone().validated();
+                if validated != settings {
+                    warn!("Some settings in {} were out of range", path.display());
+                }
+                validated
+            }
+            Ok(None) => Self::default(),
+            Err(err) => {
+                warn!("Using the default settings: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, config: &SettingsConfig) {
+        let Some(path) = &config.path else {
+            return;
+        };
+        if let Err(err) = write_ron(path, self) {
+            warn!("Settings not saved: {err}");
+        }
+    }
+}
+
+/// The distinct sizes of a monitor's video modes, largest first.
+pub fn video_mode_sizes(monitor: &Monitor) -> Vec<(u32, u32)> {
+    let mut sizes: Vec<(u32, u32)> = monitor
+        .video_modes
+        .iter()
+        .map(|mode| (mode.physical_size.x, mode.physical_size.y))
+        .collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.dedup();
+    sizes
+}
+
+/// The monitor that `Settings::monitor` refers to, or the primary one when
+/// it is `None` or no longer connected. Monitors are numbered in the order
+/// they were found.
+pub fn selected_monitor<'a>(
+    settings: &Settings,
+    monitors: impl IntoIterator<Item = (Entity, &'a Monitor, bool)>,
+) -> Option<&'a Monitor> {
+    let mut monitors: Vec<_> = monitors.into_iter().collect();
+    monitors.sort_by_key(|(entity, _, _)| *entity);
+    settings
+        .monitor
+        .and_then(|index| monitors.get(index))
+        .or_else(|| monitors.iter().find(|(_, _, primary)| *primary))
+        .or(monitors.first())
+        .map(|(_, monitor, _)| *monitor)
+}
+
+/// Loads the settings and applies them to the theme and the gameplay options
+/// whenever they change. Replays keep the options they were recorded with.
+/// With `SettingsConfig::manage_window` the window and background follow the
+/// settings too, and the windowed size and position are remembered and saved
+/// on exit.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsConfig>()
+            .init_resource::<Settings>()
+            .init_resource::<Theme>()
+            .init_resource::<GameMode>()
+            .init_resource::<StrictMisses>()
+            .add_systems(PreStartup, load_settings)
+            .add_systems(
+                Update,
+                (
+                    (apply_window_settings, apply_background).run_if(manages_window),
+                    apply_theme_settings,
+                    apply_gameplay_settings.run_if(not(resource_exists::<ReplayPlayback>)),
+                )
+                    .run_if(resource_changed::<Settings>),
+            )
+            .add_systems(Update, remember_window_geometry.run_if(manages_window))
+            .add_systems(Last, save_settings_on_exit);
+    }
+}
+
+fn manages_window(config: Res<SettingsConfig>) -> bool {
+    config.manage_window
+}
+
+fn load_settings(mut settings: ResMut<Settings>, config: Res<SettingsConfig>) {
+    *settings = Settings::load(&config);
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    // Only fields that differ are touched, so changing an unrelated setting
+    // doesn't move or resize the window.
+    for mut window in window.iter_mut() {
+        match (settings.display_mode, settings.fullscreen_resolution) {
+            (DisplayMode::Windowed, _) => {
+                let (width, height) = settings.resolution;
+                if window.resolution.width() != width as f32
+                    || window.resolution.height() != height as f32
+                {
+                    window.resolution.set(width as f32, height as f32);
+                }
+                let position = settings.window_position();
+                if window.position != position {
+                    window.position = position;
+                }
+            }
+            (DisplayMode::Fullscreen, Some((width, height)))
+                if window.resolution.physical_width() != width
+                    || window.resolution.physical_height() != height =>
+            {
+                window.resolution.set_physical_resolution(width, height);
+            }
+            _ => {}
+        }
+        let mode = settings.window_mode();
+        if window.mode != mode {
+            window.mode = mode;
+        }
+        let present_mode = settings.present_mode();
+        if window.present_mode != present_mode {
+            window.present_mode = present_mode;
+        }
+    }
+}
+
+/// Keeps track of where the player puts the window and how big they make it
+/// while it isn't fullscreen, without applying the settings again.
+fn remember_window_geometry(
+    mut resized: EventReader<WindowResized>,
+    mut moved: EventReader<WindowMoved>,
+    window: Query<(Entity, &Window), With<PrimaryWindow>>,
+    mut settings: ResMut<Settings>,
+) {
+    let Ok((entity, window)) = window.get_single() else {
+        resized.clear();
+        moved.clear();
+        return;
+    };
+    let windowed =
+        settings.display_mode == DisplayMode::Windowed && window.mode == WindowMode::Windowed;
+    let size = resized
+        .read()
+        .filter(|event| event.window == entity)
+        .last()
+        .map(|event| (event.width.round() as u32, event.height.round() as u32));
+    let position = moved
+        .read()
+        .filter(|event| event.window == entity)
+        .last()
+        .map(|event| (event.position.x, event.position.y));
+    if !windowed || (size.is_none() && position.is_none()) {
+        return;
+    }
+    let settings = settings.bypass_change_detection();
+    if let Some(size) = size {
+        settings.resolution = size;
+    }
+    if position.is_some() {
+        settings.window_position = position;
+    }
+    *settings = settings.clone().validated();
+}
+
+fn save_settings_on_exit(
+    mut exit: EventReader<AppExit>,
+    settings: Res<Settings>,
+    config: Res<SettingsConfig>,
+) {
+    if exit.read().count() > 0 {
+        settings.save(&config);
+    }
+}
+
+fn apply_theme_settings(settings: Res<Settings>, mut theme: ResMut<Theme>) {
+    theme.set_if_neq(settings.theme);
+}
+
+fn apply_background(settings: Res<Settings>, clear_color: Option<ResMut<ClearColor>>) {
+    if let Some(mut clear_color) = clear_color {
+        clear_color.0 = settings.theme.background();
+    }
+}
+
+fn apply_gameplay_settings(
+    settings: Res<Settings>,
+    mut mode: ResMut<GameMode>,
+    mut strict_misses: ResMut<StrictMisses>,
+) {
+    mode.set_if_neq(settings.mode);
+    strict_misses.set_if_neq(StrictMisses(settings.strict_misses));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::window::VideoMode;
+    use std::fs;
+
+    #[test]
+    fn the_window_opens_with_the_saved_settings() {
+        let settings = Settings {
+            resolution: (1600, 900),
+            window_position: Some((40, 60)),
+            vsync: false,
+            ..default()
+        };
+        let window = settings.window();
+        assert_eq!(window.mode, WindowMode::Windowed);
+        assert_eq!(window.resolution.width(), 1600.);
+        assert_eq!(window.resolution.height(), 900.);
+        assert_eq!(window.position, WindowPosition::At(IVec2::new(40, 60)));
+        assert_eq!(window.present_mode, PresentMode::AutoNoVsync);
+
+        let settings = Settings {
+            display_mode: DisplayMode::Fullscreen,
+            monitor: Some(1),
+            fullscreen_resolution: Some((1920, 1080)),
+            ..default()
+        };
+        let window = settings.window();
+        assert_eq!(
+            window.mode,
+            WindowMode::SizedFullscreen(MonitorSelection::Index(1))
+        );
+        assert_eq!(window.resolution.physical_width(), 1920);
+        assert_eq!(window.resolution.physical_height(), 1080);
+    }
+
+    #[test]
+    fn settings_files_are_validated_and_fill_in_missing_fields() {
+        let dir = std::env::temp_dir().join(format!("spinny-lock-settings-{}", std::process::id()));
+        let path = dir.join("settings.ron");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            &path,
+            "(resolution: (10, 100000), volume: 3.0, vsync: false)",
+        )
+        .unwrap();
+        let settings = Settings::load(&SettingsConfig {
+            path: Some(path.clone()),
+            manage_window: false,
+        });
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(settings.resolution, (320, 4320));
+        assert_eq!(settings.volume, 1.);
+        assert!(!settings.vsync);
+        assert_eq!(settings.mode, Settings::default().mode);
+        // Full volume wraps round to silent, and an unlisted size to the first preset.
+        assert_eq!(Settings::next_volume(settings.volume), 0.);
+        assert_eq!(settings.next_resolution(), (1280, 720));
+    }
+
+    #[test]
+    fn fullscreen_sizes_and_monitors_cycle_through_what_is_available() {
+        let video_mode = |width, height, refresh_rate_millihertz| VideoMode {
+            physical_size: UVec2::new(width, height),
+            bit_depth: 32,
+            refresh_rate_millihertz,
+        };
+        let monitor = Monitor {
+            name: None,
+            physical_height: 1080,
+            physical_width: 1920,
+            physical_position: IVec2::ZERO,
+            refresh_rate_millihertz: Some(60_000),
+            scale_factor: 1.,
+            video_modes: vec![
+                video_mode(1280, 720, 60_000),
+                video_mode(1920, 1080, 60_000),
+                video_mode(1920, 1080, 144_000),
+            ],
+        };
+        let mut settings = Settings::default();
+        let mut sizes = Vec::new();
+        for _ in 0..3 {
+            settings.fullscreen_resolution = settings.next_fullscreen_resolution(&monitor);
+            sizes.push(settings.fullscreen_resolution);
+        }
+        // Refresh rates of the same size are one entry, and after the smallest
+        // size comes the largest again.
+        assert_eq!(sizes, [Some((1920, 1080)), Some((1280, 720)), None]);
+
+        let mut monitors = Vec::new();
+        for _ in 0..3 {
+            settings.monitor = settings.next_monitor(2);
+            monitors.push(settings.monitor);
+        }
+        assert_eq!(monitors, [Some(0), Some(1), None]);
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    Countdown,
+    Playing,
+    /// A run on hold. Gameplay and the game clock are frozen until the player
+    /// resumes.
+    Paused,
+    GameOver,
+}
+
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    /// Misses cost a life instead of ending the run straight away.
+    Lives,
+}
+
+impl GameMode {
+    pub const ALL: &'static [GameMode] = &[GameMode::Classic, GameMode::Lives];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::Lives => "Lives",
+        }
+    }
+
+    /// The mode after this one in [`GameMode::ALL`], wrapping around.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
@@ -0,0 +1,412 @@
+use bevy::prelude::*;
+
+use crate::{
+    action::Action,
+    combo::{Combo, ComboRules},
+    gameplay::{BestScore, Countdown, Lives, RotationSpeed, RunStats, Score, TargetHit},
+    grade::Grade,
+    highscores::PendingHighScore,
+    layout::LABEL_RADIUS,
+    menu::MenuPlugin,
+    rng::GameRng,
+    state::GameState,
+};
+
+/// Grade labels appear just outside the ring, then rise and fade out.
+const GRADE_LABEL_SECONDS: f32 = 0.6;
+const GRADE_LABEL_RISE_SPEED: f32 = 80.;
+
+#[derive(Component)]
+pub struct ScoreText;
+
+/// Shown after the score while a combo is going.
+#[derive(Component)]
+struct ComboText;
+
+#[derive(Component)]
+struct SeedText;
+
+#[derive(Component)]
+struct LivesText;
+
+#[derive(Component)]
+struct Hud;
+
+#[derive(Component)]
+struct GradeLabel(Timer);
+
+#[derive(Component)]
+struct CountdownScreen;
+
+#[derive(Component)]
+struct CountdownText;
+
+#[derive(Component)]
+struct GameOverScreen;
+
+/// Hidden while a high-score name is being entered, since Enter and Esc
+/// answer the name prompt then.
+#[derive(Component)]
+struct GameOverButtons;
+
+#[derive(Component, Clone, Copy)]
+enum GameOverButton {
+    Restart,
+    MainMenu,
+}
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MenuPlugin)
+            .add_systems(OnExit(GameState::MainMenu), spawn_hud)
+            .add_systems(OnEnter(GameState::MainMenu), despawn_hud)
+            .add_systems(
+                Update,
+                (
+                    update_score_text,
+                    update_combo_text,
+                    update_seed_text,
+                    update_lives_text,
+                ),
+            )
+            .add_systems(Update, (spawn_grade_labels, float_grade_labels))
+            .add_systems(OnEnter(GameState::Countdown), spawn_countdown_text)
+            .add_systems(
+                Update,
+                update_countdown_text.run_if(in_state(GameState::Countdown)),
+            )
+            .add_systems(OnExit(GameState::Countdown), despawn_countdown_text)
+            .add_systems(OnEnter(GameState::GameOver), game_over_screen)
+            .add_systems(
+                Update,
+                (
+                    show_game_over_buttons,
+                    leave_game_over.run_if(not(resource_exists::<PendingHighScore>)),
+                )
+                    .run_if(in_state(GameState::GameOver)),
+            )
+            .add_systems(OnExit(GameState::GameOver), despawn_game_over_screen);
+    }
+}
+
+fn spawn_hud(mut commands: Commands) {
+    commands
+        .spawn((Text::new("Score: 0"), ScoreText, Hud))
+        .with_child((TextSpan::new(""), ComboText));
+    commands.spawn((
+        Text::new(""),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(24.0),
+            left: Val::Px(0.0),
+            ..default()
+        },
+        LivesText,
+        Hud,
+    ));
+    commands.spawn((
+        Text::new("Seed: "),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(0.0),
+            right: Val::Px(0.0),
+            ..default()
+        },
+        SeedText,
+        Hud,
+    ));
+}
+
+fn despawn_hud(mut commands: Commands, query: Query<Entity, With<Hud>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_score_text(score: Res<Score>, mut score_text: Query<&mut Text, With<ScoreText>>) {
+    if !score.is_changed() {
+        return;
+    }
+    for mut text in score_text.iter_mut() {
+        **text = format!("Score: {}", score.0);
+    }
+}
+
+fn update_combo_text(
+    combo: Res<Combo>,
+    rules: Res<ComboRules>,
+    mut combo_text: Query<&mut TextSpan, With<ComboText>>,
+) {
+    if !combo.is_changed() {
+        return;
+    }
+    for mut text in combo_text.iter_mut() {
+        **text = if combo.0 >= 2 {
+            format!("   Combo {} x{}", combo.0, rules.multiplier(combo.0))
+        } else {
+            String::new()
+        };
+    }
+}
+
+fn update_seed_text(game_rng: Res<GameRng>, mut seed_text: Query<&mut Text, With<SeedText>>) {
+    if !game_rng.is_changed() {
+        return;
+    }
+    for mut text in seed_text.iter_mut() {
+        **text = format!("Seed: {}", game_rng.seed());
+    }
+}
+
+fn update_lives_text(lives: Option<Res<Lives>>, mut lives_text: Query<&mut Text, With<LivesText>>) {
+    let Some(lives) = lives.filter(|lives| lives.is_changed()) else {
+        return;
+    };
+    for mut text in lives_text.iter_mut() {
+        **text = format!("Lives: {}", lives.0);
+    }
+}
+
+fn spawn_grade_labels(mut commands: Commands, mut hits: EventReader<TargetHit>) {
+    for hit in hits.read() {
+        let direction = Vec2::new(-ops::sin(hit.angle), ops::cos(hit.angle));
+        commands.spawn((
+            Text2d::new(hit.grade.name()),
+            TextFont {
+                font_size: 32.0,
+                ..default()
+            },
+            TextColor(grade_color(hit.grade)),
+            Transform::from_translation((direction * LABEL_RADIUS).extend(3.)),
+            GradeLabel(Timer::from_seconds(GRADE_LABEL_SECONDS, TimerMode::Once)),
+        ));
+    }
+}
+
+fn float_grade_labels(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut GradeLabel, &mut Transform, &mut TextColor)>,
+) {
+    for (entity, mut label, mut transform, mut color) in query.iter_mut() {
+        if label.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation.y += GRADE_LABEL_RISE_SPEED * time.delta_secs();
+        color.0.set_alpha(label.0.fraction_remaining());
+    }
+}
+
+fn grade_color(grade: Grade) -> Color {
+    match grade {
+        Grade::Perfect => Color::srgb(1.0, 0.85, 0.2),
+        Grade::Great => Color::srgb(0.3, 0.9, 1.0),
+        Grade::Good => Color::WHITE,
+    }
+}
+
+fn spawn_countdown_text(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            CountdownScreen,
+        ))
+        .with_child((
+            Text::new(""),
+            TextFont {
+                font_size: 150.0,
+                ..default()
+            },
+            CountdownText,
+        ));
+}
+
+fn update_countdown_text(
+    countdown: Res<Countdown>,
+    mut countdown_text: Query<&mut Text, With<CountdownText>>,
+) {
+    let seconds_left = countdown.0.remaining_secs().ceil().max(1.);
+    for mut text in countdown_text.iter_mut() {
+        **text = format!("{}", seconds_left);
+    }
+}
+
+fn despawn_countdown_text(mut commands: Commands, query: Query<Entity, With<CountdownScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn game_over_screen(
+    mut commands: Commands,
+    score: Res<Score>,
+    best_score: Res<BestScore>,
+    run_stats: Res<RunStats>,
+    game_rng: Res<GameRng>,
+    rotation_speed: Query<&RotationSpeed>,
+) {
+    let speed_reached = rotation_speed
+        .iter()
+        .map(|rotation_speed| rotation_speed.0.abs())
+        .fold(0., f32::max);
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            GameOverScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Game Over"),
+                TextFont {
+                    font_size: 100.0,
+                    ..default()
+                },
+                TextLayout::new_with_justify(JustifyText::Center),
+            ));
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(8.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        min_width: Val::Px(360.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                ))
+                .with_children(|panel| {
+                    spawn_result_row(panel, "Score", score.0.to_string());
+                    spawn_result_row(panel, "Best", best_score.0.to_string());
+                    spawn_result_row(
+                        panel,
+                        "Hits",
+                        format!("{} / {}", run_stats.hits, run_stats.reversals),
+                    );
+                    spawn_result_row(
+                        panel,
+                        "Perfect / Great / Good",
+                        format!(
+                            "{} / {} / {}",
+                            run_stats.perfect, run_stats.great, run_stats.good
+                        ),
+                    );
+                    spawn_result_row(panel, "Max Combo", run_stats.max_combo.to_string());
+                    spawn_result_row(panel, "Speed", format!("{:.1}", speed_reached));
+                    spawn_result_row(panel, "Seed", game_rng.seed().to_string());
+                });
+            parent
+                .spawn((
+                    Node {
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    Visibility::Hidden,
+                    GameOverButtons,
+                ))
+                .with_children(|buttons| {
+                    spawn_game_over_button(buttons, "Restart (Enter)", GameOverButton::Restart);
+                    spawn_game_over_button(buttons, "Main Menu (Esc)", GameOverButton::MainMenu);
+                });
+        });
+}
+
+fn spawn_game_over_button(parent: &mut ChildBuilder, label: &str, button: GameOverButton) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(20.0), Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+            button,
+        ))
+        .with_child((
+            Text::new(label),
+            TextFont {
+                font_size: 40.0,
+                ..default()
+            },
+        ));
+}
+
+fn spawn_result_row(parent: &mut ChildBuilder, label: &str, value: String) {
+    parent
+        .spawn(Node {
+            justify_content: JustifyContent::SpaceBetween,
+            column_gap: Val::Px(40.0),
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            row.spawn((
+                Text::new(value),
+                TextFont {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn show_game_over_buttons(
+    pending: Option<Res<PendingHighScore>>,
+    mut buttons: Query<&mut Visibility, With<GameOverButtons>>,
+) {
+    let visibility = if pending.is_some() {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for mut buttons_visibility in buttons.iter_mut() {
+        buttons_visibility.set_if_neq(visibility);
+    }
+}
+
+fn leave_game_over(
+    actions: Res<ButtonInput<Action>>,
+    buttons: Query<(&Interaction, &GameOverButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pressed = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| *button);
+    if actions.just_pressed(Action::Confirm) || matches!(pressed, Some(GameOverButton::Restart)) {
+        next_state.set(GameState::Countdown);
+    } else if actions.just_pressed(Action::Back)
+        || matches!(pressed, Some(GameOverButton::MainMenu))
+    {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn despawn_game_over_screen(mut commands: Commands, query: Query<Entity, With<GameOverScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
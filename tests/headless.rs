@@ -0,0 +1,889 @@
+use bevy::{
+    prelude::*,
+    time::TimeUpdateStrategy,
+    window::{PrimaryWindow, WindowFocused, WindowResized},
+};
+use spinny_lock::{
+    wrap_angle, Action, Behaviour, Binding, Bindings, Combo, ComboRules, DifficultyCurve,
+    DifficultyKeyframe, DisplayMode, GameCamera, GameMode, GameState, GameplayPlugin, Grade,
+    HeadlessPlugin, HighScoreConfig, HighScorePlugin, HighScores, InputPlugin, Invulnerable,
+    Layout, Lives, Replay, ReplayConfig, ReplayPlayback, ReplayPlugin, RingAngle, RngSeed,
+    RotationSpeed, RunStats, Score, Settings, SettingsConfig, StartingLives, StrictMisses,
+    TargetOrder, TargetZone, WindowFocus, HEADLESS_TICK_RATE, RING_RADIUS,
+};
+use std::{f32::consts::PI, fs, time::Duration};
+
+/// Builds a headless app around `GameplayPlugin`. Resources inserted along
+/// the way replace the plugins' defaults.
+struct TestGame(App);
+
+impl TestGame {
+    fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins(HeadlessPlugin);
+        Self(app)
+    }
+
+    fn seed(self, seed: u64) -> Self {
+        self.with(RngSeed(Some(seed)))
+    }
+
+    fn curve(self, keyframes: Vec<DifficultyKeyframe>) -> Self {
+        self.with(DifficultyCurve {
+            keyframes,
+            ..default()
+        })
+    }
+
+    fn with(mut self, resource: impl Resource) -> Self {
+        self.0.insert_resource(resource);
+        self
+    }
+
+    fn plugin(mut self, plugin: impl Plugin) -> Self {
+        self.0.add_plugins(plugin);
+        self
+    }
+
+    /// Adds the gameplay without starting a run.
+    fn build(mut self) -> App {
+        self.0.add_plugins(GameplayPlugin);
+        self.0
+    }
+
+    fn start(self) -> App {
+        let mut app = self.build();
+        start_run(&mut app);
+        app
+    }
+}
+
+/// Leaves the current state through the countdown and stops right after the
+/// first gameplay tick, which places the first target.
+fn start_run(app: &mut App) {
+    app.update();
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Countdown);
+    wait_until(app, |app| game_state(app) == GameState::Playing);
+}
+
+fn press_key(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(key);
+    app.update();
+    let mut keyboard = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keyboard.release(key);
+    keyboard.clear();
+}
+
+fn press_space(app: &mut App) {
+    press_key(app, KeyCode::Space);
+}
+
+fn line_angle(app: &mut App) -> f32 {
+    let mut query = app
+        .world_mut()
+        .query_filtered::<&RingAngle, With<RotationSpeed>>();
+    query.single(app.world()).current
+}
+
+fn target_angle(app: &mut App) -> f32 {
+    let mut query = app
+        .world_mut()
+        .query_filtered::<&RingAngle, With<TargetZone>>();
+    query.single(app.world()).current
+}
+
+fn line_speed(app: &mut App) -> f32 {
+    let mut query = app.world_mut().query::<&RotationSpeed>();
+    query.single(app.world()).0
+}
+
+fn set_target_angle(app: &mut App, angle: f32) {
+    let mut query = app
+        .world_mut()
+        .query_filtered::<&mut RingAngle, With<TargetZone>>();
+    for mut target_angle in query.iter_mut(app.world_mut()) {
+        target_angle.teleport(angle);
+    }
+}
+
+fn target_half_widths(app: &mut App) -> Vec<f32> {
+    let mut query = app.world_mut().query::<&TargetZone>();
+    query
+        .iter(app.world())
+        .map(|target| target.half_width)
+        .collect()
+}
+
+fn keyframe(hits: u32, speed: f32, arc_width: f32, targets: u32) -> DifficultyKeyframe {
+    DifficultyKeyframe {
+        hits,
+        speed,
+        arc_width,
+        targets,
+        order: TargetOrder::AnyOrder,
+        behaviours: Vec::new(),
+    }
+}
+
+/// Angle and whether it has been hit for every target, in hit order.
+fn targets(app: &mut App) -> Vec<(f32, bool)> {
+    let mut query = app.world_mut().query::<(&TargetZone, &RingAngle)>();
+    let mut targets: Vec<_> = query
+        .iter(app.world())
+        .map(|(target, angle)| (target.order, angle.current, target.hit))
+        .collect();
+    targets.sort_by_key(|(order, _, _)| *order);
+    targets
+        .into_iter()
+        .map(|(_, angle, hit)| (angle, hit))
+        .collect()
+}
+
+fn move_target_under_line(app: &mut App, order: u32) {
+    let angle = line_angle(app);
+    let mut query = app.world_mut().query::<(&TargetZone, &mut RingAngle)>();
+    for (target, mut target_angle) in query.iter_mut(app.world_mut()) {
+        if target.order == order {
+            target_angle.teleport(angle);
+        } else {
+            target_angle.teleport(angle + PI);
+        }
+    }
+}
+
+fn line_to_target(app: &mut App) -> f32 {
+    wrap_angle(line_angle(app) - target_angle(app)).abs()
+}
+
+fn wait_until(app: &mut App, condition: impl Fn(&mut App) -> bool) {
+    for _ in 0..2000 {
+        if condition(app) {
+            return;
+        }
+        app.update();
+    }
+    panic!("condition was never met");
+}
+
+fn game_state(app: &App) -> GameState {
+    app.world().resource::<State<GameState>>().get().clone()
+}
+
+#[test]
+fn line_rotates_by_speed_times_fixed_frame_time() {
+    let mut app = TestGame::new().seed(1).start();
+    let speed = line_speed(&mut app);
+    let before = line_angle(&mut app);
+
+    app.update();
+
+    let expected = -speed / HEADLESS_TICK_RATE as f32;
+    assert!((wrap_angle(line_angle(&mut app) - before) - expected).abs() < 1e-4);
+}
+
+#[test]
+fn pressing_inside_the_target_scores() {
+    let mut app = TestGame::new().seed(2).start();
+    let speed = line_speed(&mut app);
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle);
+
+    press_space(&mut app);
+
+    assert_eq!(app.world().resource::<Score>().0, 3);
+    assert_eq!(app.world().resource::<RunStats>().hits, 1);
+    assert_eq!(app.world().resource::<RunStats>().perfect, 1);
+    assert_eq!(line_speed(&mut app).signum(), -speed.signum());
+    app.update();
+    assert_eq!(game_state(&app), GameState::Playing);
+}
+
+#[test]
+fn clicking_reverses_the_line_like_space() {
+    let mut app = TestGame::new().seed(2).start();
+    let speed = line_speed(&mut app);
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle);
+
+    let mut mouse = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+    mouse.press(MouseButton::Left);
+    app.update();
+    app.world_mut()
+        .resource_mut::<ButtonInput<MouseButton>>()
+        .clear();
+
+    assert_eq!(app.world().resource::<RunStats>().hits, 1);
+    assert_eq!(line_speed(&mut app).signum(), -speed.signum());
+
+    // Holding the button down doesn't reverse again.
+    app.update();
+    assert_eq!(app.world().resource::<RunStats>().reversals, 1);
+}
+
+#[test]
+fn actions_follow_rebound_inputs() {
+    let mut app = TestGame::new().seed(2).start();
+    let mut bindings = app.world_mut().resource_mut::<Bindings>();
+    bindings.clear(Action::Reverse);
+    bindings.bind(Action::Reverse, Binding::Key(KeyCode::KeyJ));
+    bindings.bind(Action::Reverse, Binding::Key(KeyCode::KeyK));
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle);
+
+    press_space(&mut app);
+    assert_eq!(app.world().resource::<RunStats>().reversals, 0);
+    press_key(&mut app, KeyCode::KeyK);
+    assert_eq!(app.world().resource::<RunStats>().hits, 1);
+}
+
+#[test]
+fn the_camera_keeps_the_ring_in_view_when_the_window_is_resized() {
+    let mut app = TestGame::new().build();
+    let window = app
+        .world_mut()
+        .spawn((Window::default(), PrimaryWindow))
+        .id();
+    // A camera of the host app's own, which should keep its projection.
+    let host_camera = app.world_mut().spawn(Camera2d).id();
+    app.update();
+
+    let view_radius = |app: &mut App, width: f32, height: f32| {
+        app.world_mut().send_event(WindowResized {
+            window,
+            width,
+            height,
+        });
+        app.update();
+        let ring_radius = app.world().resource::<Layout>().ring_radius;
+        let scale = app
+            .world_mut()
+            .query_filtered::<&OrthographicProjection, With<GameCamera>>()
+            .single(app.world())
+            .scale;
+        // Half the window's smaller side, in world units.
+        (ring_radius, width.min(height) / 2. * scale)
+    };
+    let (small_ring, small_view) = view_radius(&mut app, 400., 300.);
+    let (large_ring, large_view) = view_radius(&mut app, 2560., 1440.);
+    assert!(large_ring > small_ring);
+    // The same amount of the world is in view, so the ring fits either way.
+    assert!((small_view - large_view).abs() < 0.01);
+    assert!(small_view > RING_RADIUS);
+    let host_projection = app.world().get::<OrthographicProjection>(host_camera);
+    assert_eq!(host_projection.unwrap().scale, 1.);
+}
+
+#[test]
+fn a_camera_tagged_by_the_host_is_used_instead_of_spawning_one() {
+    let mut app = TestGame::new().build();
+    app.add_systems(Startup, |mut commands: Commands| {
+        commands.spawn((Camera2d, GameCamera));
+    });
+    app.update();
+    let cameras = app
+        .world_mut()
+        .query::<&Camera2d>()
+        .iter(app.world())
+        .count();
+    assert_eq!(cameras, 1);
+
+    let mut app = TestGame::new().build();
+    app.update();
+    let game_cameras = app
+        .world_mut()
+        .query_filtered::<(), (With<Camera2d>, With<GameCamera>)>()
+        .iter(app.world())
+        .count();
+    assert_eq!(game_cameras, 1);
+}
+
+#[test]
+fn hits_are_graded_by_distance_from_the_target_centre() {
+    let mut app = TestGame::new()
+        .seed(8)
+        .curve(vec![keyframe(0, 1., 40., 1)])
+        .start();
+    let half_width = target_half_widths(&mut app)[0];
+    for (offset, points) in [(half_width, 1), (half_width * 0.4, 3), (0., 6)] {
+        let angle = line_angle(&mut app);
+        set_target_angle(&mut app, angle + offset);
+        press_space(&mut app);
+        assert_eq!(app.world().resource::<Score>().0, points);
+    }
+
+    let run_stats = app.world().resource::<RunStats>();
+    assert_eq!(
+        (run_stats.perfect, run_stats.great, run_stats.good),
+        (1, 1, 1)
+    );
+}
+
+#[test]
+fn consecutive_hits_build_a_combo_multiplier() {
+    let mut app = TestGame::new()
+        .seed(15)
+        .curve(vec![keyframe(0, 1., 40., 1)])
+        .with(ComboRules {
+            hits_per_step: 2,
+            max_multiplier: 3,
+            lowest_grade: Grade::Great,
+            break_on_miss: true,
+        })
+        .start();
+    let half_width = target_half_widths(&mut app)[0];
+
+    // Perfect hits are worth 3, doubled from the third hit on. The good hit
+    // breaks the combo and scores without the multiplier.
+    for (offset, score) in [(0., 3), (0., 6), (0., 12), (half_width, 13), (0., 16)] {
+        let angle = line_angle(&mut app);
+        set_target_angle(&mut app, angle + offset);
+        press_space(&mut app);
+        assert_eq!(app.world().resource::<Score>().0, score);
+    }
+    assert_eq!(app.world().resource::<Combo>().0, 1);
+    assert_eq!(app.world().resource::<RunStats>().max_combo, 3);
+}
+
+#[test]
+fn a_combo_does_not_skip_ahead_on_the_difficulty_curve() {
+    let mut app = TestGame::new()
+        .seed(15)
+        .curve(vec![keyframe(0, 2., 40., 1), keyframe(10, 12., 40., 1)])
+        .with(ComboRules {
+            hits_per_step: 1,
+            max_multiplier: 4,
+            ..default()
+        })
+        .start();
+
+    // The score races ahead with the multiplier, but each hit only moves one
+    // step along the curve.
+    for hits in 1..=5 {
+        let angle = line_angle(&mut app);
+        set_target_angle(&mut app, angle);
+        press_space(&mut app);
+        assert!((line_speed(&mut app).abs() - (2. + hits as f32)).abs() < 1e-5);
+    }
+    assert_eq!(app.world().resource::<Score>().0, 3 + 6 + 9 + 12 + 12);
+}
+
+#[test]
+fn a_press_during_a_long_frame_lands_on_the_tick_it_was_seen() {
+    let mut app = TestGame::new().seed(5).start();
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle);
+
+    // A long hitch runs a dozen or so ticks in one frame, enough to carry the
+    // line well past the target before the frame ends.
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        500,
+    )));
+    press_space(&mut app);
+
+    // Landing on the tick the target was under the line makes it a perfect hit.
+    assert_eq!(app.world().resource::<Score>().0, 3);
+}
+
+#[test]
+fn pressing_outside_the_target_ends_the_game() {
+    let mut app = TestGame::new().seed(3).start();
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle + PI);
+
+    press_space(&mut app);
+    app.update();
+
+    assert_eq!(app.world().resource::<Score>().0, 0);
+    assert_eq!(game_state(&app), GameState::GameOver);
+}
+
+#[test]
+fn in_lives_mode_a_miss_costs_a_life() {
+    let mut app = TestGame::new()
+        .seed(17)
+        .with(GameMode::Lives)
+        .with(StartingLives(2))
+        .start();
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle + PI);
+
+    press_space(&mut app);
+    app.update();
+    assert_eq!(app.world().resource::<Lives>().0, 1);
+    assert!(app.world().contains_resource::<Invulnerable>());
+    assert_eq!(game_state(&app), GameState::Playing);
+
+    // Misses right after losing a life are forgiven.
+    press_space(&mut app);
+    assert_eq!(app.world().resource::<Lives>().0, 1);
+
+    wait_until(&mut app, |app| {
+        !app.world().contains_resource::<Invulnerable>()
+    });
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle + PI);
+    press_space(&mut app);
+    app.update();
+    assert_eq!(app.world().resource::<Lives>().0, 0);
+    assert_eq!(game_state(&app), GameState::GameOver);
+}
+
+#[test]
+fn pausing_while_the_ring_flashes_shows_the_ring() {
+    let mut app = TestGame::new()
+        .seed(17)
+        .with(GameMode::Lives)
+        .with(StartingLives(2))
+        .start();
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle + PI);
+    press_space(&mut app);
+
+    // Only the ring has a mesh without being the line or a target.
+    let ring_visibility = |app: &mut App| {
+        *app.world_mut()
+            .query_filtered::<&Visibility, (With<Mesh2d>, Without<TargetZone>, Without<RotationSpeed>)>()
+            .single(app.world())
+    };
+    wait_until(&mut app, |app| ring_visibility(app) == Visibility::Hidden);
+    press_key(&mut app, KeyCode::Escape);
+    app.update();
+    assert_eq!(game_state(&app), GameState::Paused);
+    assert_eq!(ring_visibility(&mut app), Visibility::Inherited);
+}
+
+#[test]
+fn with_strict_misses_letting_the_line_pass_a_target_is_a_miss() {
+    for strict in [false, true] {
+        let mut app = TestGame::new().seed(6).with(StrictMisses(strict)).start();
+        let angle = line_angle(&mut app);
+        set_target_angle(&mut app, angle);
+
+        wait_until(&mut app, |app| {
+            game_state(app) != GameState::Playing || line_to_target(app) > 1.
+        });
+        let expected = if strict {
+            GameState::GameOver
+        } else {
+            GameState::Playing
+        };
+        assert_eq!(game_state(&app), expected);
+    }
+}
+
+#[test]
+fn leaving_game_over_resets_the_run() {
+    let mut app = TestGame::new().seed(4).start();
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle);
+    press_space(&mut app);
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle + PI);
+    press_space(&mut app);
+    app.update();
+    assert_eq!(game_state(&app), GameState::GameOver);
+
+    start_run(&mut app);
+
+    assert_eq!(app.world().resource::<Score>().0, 0);
+    assert_eq!(app.world().resource::<RunStats>().reversals, 0);
+    assert_eq!(line_speed(&mut app), 1.5);
+}
+
+#[test]
+fn pausing_freezes_the_line_until_resumed() {
+    let mut app = TestGame::new().seed(6).start();
+    press_key(&mut app, KeyCode::Escape);
+    app.update();
+    assert_eq!(game_state(&app), GameState::Paused);
+
+    let angle = line_angle(&mut app);
+    let elapsed = app.world().resource::<Time<Virtual>>().elapsed();
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(line_angle(&mut app), angle);
+    assert_eq!(app.world().resource::<Time<Virtual>>().elapsed(), elapsed);
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+    app.update();
+    assert_ne!(line_angle(&mut app), angle);
+}
+
+#[test]
+fn losing_focus_pauses_the_game() {
+    let mut app = TestGame::new().seed(8).start();
+    let window = app.world_mut().spawn(PrimaryWindow).id();
+    app.world_mut().send_event(WindowFocused {
+        window,
+        focused: false,
+    });
+    app.update();
+    app.update();
+
+    assert_eq!(game_state(&app), GameState::Paused);
+}
+
+#[test]
+fn losing_focus_during_the_countdown_pauses_the_run_when_it_starts() {
+    let mut app = TestGame::new().seed(8).build();
+    let window = app.world_mut().spawn(PrimaryWindow).id();
+    // Focus changes of other windows don't count.
+    let other_window = app.world_mut().spawn_empty().id();
+    app.update();
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Countdown);
+    app.update();
+    app.world_mut().send_event(WindowFocused {
+        window: other_window,
+        focused: false,
+    });
+    app.update();
+    assert!(app.world().resource::<WindowFocus>().0);
+
+    app.world_mut().send_event(WindowFocused {
+        window,
+        focused: false,
+    });
+    wait_until(&mut app, |app| game_state(app) != GameState::Countdown);
+    app.update();
+    assert_eq!(game_state(&app), GameState::Paused);
+}
+
+#[test]
+fn the_difficulty_curve_sets_speed_arc_width_and_target_count() {
+    let mut app = TestGame::new()
+        .seed(9)
+        .curve(vec![keyframe(0, 2., 90., 1), keyframe(2, 4., 30., 3)])
+        .start();
+    assert_eq!(line_speed(&mut app).abs(), 2.);
+    assert_eq!(target_half_widths(&mut app), vec![45_f32.to_radians()]);
+
+    // One hit is halfway to the next keyframe, whatever it scored, so speed
+    // and width are interpolated halfway.
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle);
+    press_space(&mut app);
+    assert!((line_speed(&mut app).abs() - 3.).abs() < 1e-5);
+    assert_eq!(target_half_widths(&mut app), vec![30_f32.to_radians()]);
+
+    let angle = line_angle(&mut app);
+    set_target_angle(&mut app, angle);
+    press_space(&mut app);
+    assert_eq!(line_speed(&mut app).abs(), 4.);
+    assert_eq!(target_half_widths(&mut app), vec![15_f32.to_radians(); 3]);
+}
+
+#[test]
+fn shrinking_targets_stop_at_the_minimum_width() {
+    let mut shrinking = keyframe(0, 1., 40., 1);
+    shrinking.behaviours.push(Behaviour::Shrink(60.));
+    let mut app = TestGame::new()
+        .seed(10)
+        .with(DifficultyCurve {
+            keyframes: vec![shrinking],
+            min_arc_width: 20.,
+        })
+        .start();
+
+    // 60 degrees per second narrows the arc by one degree per tick.
+    for _ in 0..10 {
+        app.update();
+    }
+    let half_width = target_half_widths(&mut app)[0].to_degrees();
+    assert!((half_width - 15.).abs() < 0.1, "{half_width}");
+
+    for _ in 0..60 {
+        app.update();
+    }
+    assert_eq!(target_half_widths(&mut app), vec![10_f32.to_radians()]);
+}
+
+#[test]
+fn targets_are_placed_without_overlapping() {
+    let mut app = TestGame::new()
+        .seed(11)
+        .curve(vec![keyframe(0, 1., 40., 5)])
+        .start();
+    let half_width = 20_f32.to_radians();
+    for _ in 0..10 {
+        let targets = targets(&mut app);
+        assert_eq!(targets.len(), 5);
+        for (i, (a, _)) in targets.iter().enumerate() {
+            for (b, _) in &targets[i + 1..] {
+                assert!(wrap_angle(a - b).abs() > 2. * half_width);
+            }
+        }
+        for order in 0..5 {
+            move_target_under_line(&mut app, order);
+            press_space(&mut app);
+        }
+    }
+}
+
+#[test]
+fn each_target_scores_once_per_round() {
+    let mut app = TestGame::new()
+        .seed(12)
+        .curve(vec![keyframe(0, 1., 40., 2)])
+        .start();
+    move_target_under_line(&mut app, 1);
+    press_space(&mut app);
+    assert_eq!(app.world().resource::<Score>().0, 3);
+    assert_eq!(
+        targets(&mut app)
+            .iter()
+            .map(|(_, hit)| *hit)
+            .collect::<Vec<_>>(),
+        vec![false, true]
+    );
+
+    // Pressing inside a target that was already hit is a miss.
+    move_target_under_line(&mut app, 1);
+    press_space(&mut app);
+    app.update();
+    assert_eq!(app.world().resource::<Score>().0, 3);
+    assert_eq!(game_state(&app), GameState::GameOver);
+}
+
+#[test]
+fn clearing_a_round_places_new_targets() {
+    let mut app = TestGame::new()
+        .seed(13)
+        .curve(vec![keyframe(0, 1., 40., 2)])
+        .start();
+    move_target_under_line(&mut app, 0);
+    press_space(&mut app);
+    move_target_under_line(&mut app, 1);
+    press_space(&mut app);
+
+    assert_eq!(app.world().resource::<RunStats>().hits, 2);
+    assert!(targets(&mut app).iter().all(|(_, hit)| !hit));
+    assert_eq!(game_state(&app), GameState::Playing);
+}
+
+#[test]
+fn in_order_targets_must_be_hit_in_order() {
+    let mut in_order = keyframe(0, 1., 40., 2);
+    in_order.order = TargetOrder::InOrder;
+    let mut app = TestGame::new().seed(14).curve(vec![in_order]).start();
+    move_target_under_line(&mut app, 1);
+    press_space(&mut app);
+    app.update();
+
+    assert_eq!(app.world().resource::<Score>().0, 0);
+    assert_eq!(game_state(&app), GameState::GameOver);
+}
+
+#[test]
+fn target_motions_can_be_mixed() {
+    let mut moving = keyframe(0, 1., 40., 1);
+    moving.behaviours = vec![
+        Behaviour::Drift(60.),
+        Behaviour::Oscillate {
+            amplitude: 30.,
+            period: 1.,
+        },
+    ];
+    let mut app = TestGame::new().seed(15).curve(vec![moving]).start();
+    let start = target_angle(&mut app);
+
+    // A quarter period in: 15 degrees of drift plus the full swing.
+    for _ in 0..15 {
+        app.update();
+    }
+    let moved = wrap_angle(target_angle(&mut app) - start).to_degrees();
+    assert!((moved - 45.).abs() < 0.1, "{moved}");
+
+    // A full period in the swing is back where it started.
+    for _ in 0..45 {
+        app.update();
+    }
+    let moved = wrap_angle(target_angle(&mut app) - start).to_degrees();
+    assert!((moved - 60.).abs() < 0.1, "{moved}");
+}
+
+#[test]
+fn teleporting_targets_jump_on_a_timer() {
+    let mut teleporting = keyframe(0, 1., 40., 1);
+    teleporting.behaviours = vec![Behaviour::Teleport(0.5)];
+    let mut app = TestGame::new().seed(16).curve(vec![teleporting]).start();
+    let start = target_angle(&mut app);
+
+    for _ in 0..29 {
+        app.update();
+    }
+    assert_eq!(target_angle(&mut app), start);
+    app.update();
+    assert_ne!(target_angle(&mut app), start);
+}
+
+#[test]
+fn same_seed_and_inputs_place_targets_identically() {
+    let mut first = TestGame::new().seed(42).start();
+    let mut second = TestGame::new().seed(42).start();
+
+    for _ in 0..5 {
+        assert_eq!(target_angle(&mut first), target_angle(&mut second));
+        for app in [&mut first, &mut second] {
+            let angle = line_angle(app);
+            set_target_angle(app, angle);
+            press_space(app);
+        }
+    }
+    assert_eq!(target_angle(&mut first), target_angle(&mut second));
+    assert_eq!(first.world().resource::<Score>().0, 15);
+}
+
+#[test]
+fn replays_reproduce_the_recorded_run() {
+    let directory = std::env::temp_dir().join(format!("spinny-lock-replay-{}", std::process::id()));
+    let mut app = TestGame::new()
+        .seed(7)
+        .with(ReplayConfig {
+            directory: directory.clone(),
+        })
+        .plugin(ReplayPlugin)
+        .start();
+
+    for _ in 0..3 {
+        wait_until(&mut app, |app| line_to_target(app) < 0.2);
+        press_space(&mut app);
+    }
+    wait_until(&mut app, |app| line_to_target(app) > 1.5);
+    press_space(&mut app);
+    app.update();
+    assert_eq!(game_state(&app), GameState::GameOver);
+    let score = app.world().resource::<Score>().0;
+
+    let replay_path = fs::read_dir(&directory)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let replay = Replay::load(&replay_path).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(replay.seed, 7);
+    assert_eq!(replay.score, score);
+    assert_eq!(replay.inputs.len(), 4);
+
+    let mut playback = TestGame::new()
+        .with(ReplayPlayback(replay))
+        .plugin(ReplayPlugin)
+        .build();
+    // Playback starts the countdown on its own.
+    wait_until(&mut playback, |app| game_state(app) == GameState::GameOver);
+
+    assert_eq!(playback.world().resource::<Score>().0, score);
+    assert_eq!(target_angle(&mut playback), target_angle(&mut app));
+}
+
+#[test]
+fn the_line_starts_at_the_curve_speed_during_the_countdown() {
+    let mut app = TestGame::new()
+        .curve(vec![keyframe(0, 2.5, 40., 1)])
+        .build();
+    app.update();
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Countdown);
+    app.update();
+    assert_eq!(game_state(&app), GameState::Countdown);
+    assert_eq!(line_speed(&mut app).abs(), 2.5);
+
+    wait_until(&mut app, |app| game_state(app) == GameState::Playing);
+    assert_eq!(line_speed(&mut app).abs(), 2.5);
+}
+
+#[test]
+fn the_input_plugin_works_on_its_own() {
+    let mut app = App::new();
+    app.add_plugins(HeadlessPlugin).add_plugins(InputPlugin);
+    app.update();
+    press_key(&mut app, KeyCode::F12);
+    app.update();
+    assert_eq!(
+        app.world().resource::<Settings>().display_mode,
+        DisplayMode::Fullscreen
+    );
+}
+
+#[test]
+fn the_host_window_is_left_alone_unless_asked() {
+    for manage_window in [false, true] {
+        let mut app = App::new();
+        app.add_plugins(HeadlessPlugin)
+            .insert_resource(SettingsConfig {
+                path: None,
+                manage_window,
+            })
+            .insert_resource(ClearColor(Color::WHITE))
+            .add_plugins(InputPlugin);
+        let window = app
+            .world_mut()
+            .spawn((Window::default(), PrimaryWindow))
+            .id();
+        app.world_mut()
+            .get_mut::<Window>(window)
+            .unwrap()
+            .resolution
+            .set(800., 600.);
+        app.update();
+
+        let width = app.world().get::<Window>(window).unwrap().width();
+        let clear_color = app.world().resource::<ClearColor>().0;
+        if manage_window {
+            assert_eq!(width, Settings::default().resolution.0 as f32);
+            assert_ne!(clear_color, Color::WHITE);
+        } else {
+            assert_eq!(width, 800.);
+            assert_eq!(clear_color, Color::WHITE);
+        }
+    }
+}
+
+#[test]
+fn back_skips_entering_a_high_score_name() {
+    for skip in [false, true] {
+        let dir = std::env::temp_dir().join(format!(
+            "spinny-lock-name-entry-{skip}-{}",
+            std::process::id()
+        ));
+        let mut app = TestGame::new()
+            .seed(4)
+            .with(HighScoreConfig {
+                path: dir.join("highscores.ron"),
+                capacity: 5,
+            })
+            .plugin(HighScorePlugin)
+            .start();
+        let angle = line_angle(&mut app);
+        set_target_angle(&mut app, angle);
+        press_space(&mut app);
+        let angle = line_angle(&mut app);
+        set_target_angle(&mut app, angle + PI);
+        press_space(&mut app);
+        app.update();
+        assert_eq!(game_state(&app), GameState::GameOver);
+
+        if skip {
+            press_key(&mut app, KeyCode::Escape);
+        }
+        press_key(&mut app, KeyCode::Enter);
+        app.update();
+        let saved = app.world().resource::<HighScores>().entries.len();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(saved, if skip { 0 } else { 1 });
+        assert_eq!(game_state(&app), GameState::GameOver);
+    }
+}